@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::iter::StepBy;
+use std::ops::RangeInclusive;
+
+use crate::items::{get_ids_in, ItemCategory};
+
+/// iterate over the locations of the 12 byte chunks of rowf's shop data
+pub fn get_rowf_iter() -> StepBy<RangeInclusive<u32>> {
+    (0x808808..=0x8088C7).step_by(12)
+}
+
+/// iterate over the locations of the 12 byte chunks of merlow's shop data
+pub fn get_merlow_iter() -> StepBy<RangeInclusive<u32>> {
+    (0xA3CACC..=0xA3CB7F).step_by(12)
+}
+
+pub fn get_badges_set() -> HashSet<u32> {
+    get_badges().into_iter().collect()
+}
+
+/// Every id in `get_badges_set`, in id order.
+pub fn get_badges() -> Vec<u32> {
+    get_ids_in(ItemCategory::Badge)
+}
+
+/// the set from `get_badges_set` includes some badges that were not used in the game.
+/// This contains only the used ones, and duplicates where there were ones in the game.
+pub fn get_used_badges() -> Vec<u32> {
+    vec![
+        0x00ed,
+        0x00ed,
+        0x012f,
+        0x0124,
+        0x0120,
+        0x0114,
+        0x0121,
+        0x0112,
+        0x0107,
+        0x00e8,
+        0x013a,
+        0x0103,
+        0x011c,
+        0x0148,
+        0x00e5,
+        0x00f7,
+        0x0104,
+        0x0135,
+        0x0109,
+        0x00e4,
+        0x0134,
+        0x011d,
+        0x0146,
+        0x0146,
+        0x0133,
+        0x0133,
+        0x010d,
+        0x0129,
+        0x0111,
+        0x0117,
+        0x0136,
+        0x00e7,
+        0x00f8,
+        0x012e,
+        0x00f9,
+        0x00ec,
+        0x00ef,
+        0x0141,
+        0x0126,
+        0x010c,
+        0x0138,
+        0x0138,
+        0x0147,
+        0x0123,
+        0x0116,
+        0x0113,
+        0x0106,
+        0x00fb,
+        0x00fa,
+        0x0132,
+        0x013c,
+    ]
+}
+
+/// Those badges that are found on the map, (AKA not in Rowf's or Merlow's shops, or through
+/// conversations)
+pub fn get_map_badges() -> Vec<u32> {
+    vec![
+        0x00ed,
+        0x00ed,
+        0x012f,
+        0x0124,
+        0x0120,
+        0x0114,
+        0x0121,
+        0x0112,
+        0x0107,
+        0x00e8,
+        0x013a,
+        0x0103,
+        0x011c,
+        0x0148,
+        0x00e5,
+        0x00f7,
+        0x0104,
+        0x0135,
+        0x0109,
+        0x00e4,
+        0x0134,
+        0x011d,
+        0x0146,
+        0x0146,
+        0x0133,
+        0x0133,
+        0x010d,
+        0x0129,
+        0x0111,
+        0x0117,
+        0x0136,
+        0x00e7,
+        0x00f8,
+        0x012e,
+        0x00f9,
+        0x00ec,
+        0x00ef,
+        0x0141,
+        0x0126,
+        0x010c,
+        0x0138,
+        0x0138,
+        0x0147,
+        0x0123,
+        0x0116,
+        0x0113,
+        0x0106,
+        0x00fb,
+        0x00fa,
+        0x0132,
+        0x013c,
+    ]
+}
+
+/// Those badges that are found in Rowf's shop.
+/// Does not include the I Spy badge which Rowf gives for returning his calculator.
+pub fn get_rowf_shop_badges() -> Vec<u32> {
+    vec![
+        0x011f,
+        0x00e6,
+        0x00e1,
+        0x00ee,
+        0x00e3,
+        0x00eb,
+        0x00e9,
+        0x010a,
+        0x00e0,
+        0x0143,
+        0x00f4,
+        0x014a,
+        0x014d,
+        0x010b,
+        0x0130,
+        0x0108,
+    ]
+}
+
+/// Those badges that are found in Merlow's shop.
+pub fn get_merlow_shop_badges() -> Vec<u32> {
+    vec![
+        0x00f3,
+        0x00fc,
+        0x00fe,
+        0x00f1,
+        0x00f2,
+        0x00ff,
+        0x0140,
+        0x0144,
+        0x0100,
+        0x011a,
+        0x011b,
+        0x00f5,
+        0x00fd,
+        0x0105,
+        0x00e2,
+    ]
+}
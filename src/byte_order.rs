@@ -0,0 +1,62 @@
+// N64 rom dumps come in three byte orders, which can be told apart by the first word of the
+// header, which is always 0x80371240 in the native big-endian order.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// .z64 files. This is the order the rest of the program expects.
+    BigEndian,
+    /// .v64 files. Each pair of bytes is swapped.
+    ByteSwapped,
+    /// .n64 files. Each four byte word is reversed.
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// The conventional file extension for this order.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ByteOrder::BigEndian => "z64",
+            ByteOrder::ByteSwapped => "v64",
+            ByteOrder::LittleEndian => "n64",
+        }
+    }
+}
+
+pub fn detect(rom: &[u8]) -> Option<ByteOrder> {
+    use ByteOrder::*;
+    match rom.get(0..4)? {
+        [0x80, 0x37, 0x12, 0x40] => Some(BigEndian),
+        [0x37, 0x80, 0x40, 0x12] => Some(ByteSwapped),
+        [0x40, 0x12, 0x37, 0x80] => Some(LittleEndian),
+        _ => None,
+    }
+}
+
+/// Convert between big-endian and `order`. Each conversion is its own inverse, so this works
+/// in either direction.
+pub fn convert(rom: &mut [u8], order: ByteOrder) {
+    match order {
+        ByteOrder::BigEndian => {}
+        ByteOrder::ByteSwapped => {
+            for pair in rom.chunks_exact_mut(2) {
+                pair.swap(0, 1);
+            }
+        }
+        ByteOrder::LittleEndian => {
+            for word in rom.chunks_exact_mut(4) {
+                word.reverse();
+            }
+        }
+    }
+}
+
+#[test]
+fn every_order_is_detected_after_converting() {
+    for &order in [ByteOrder::BigEndian, ByteOrder::ByteSwapped, ByteOrder::LittleEndian].iter() {
+        let mut rom = vec![0x80, 0x37, 0x12, 0x40, 1, 2, 3, 4];
+        convert(&mut rom, order);
+        assert_eq!(detect(&rom), Some(order));
+        convert(&mut rom, order);
+        assert_eq!(rom, vec![0x80, 0x37, 0x12, 0x40, 1, 2, 3, 4]);
+    }
+}
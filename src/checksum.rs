@@ -0,0 +1,147 @@
+// A port of the header checksum calculation done by rn64crc, which we used to ship and shell out
+// to. See http://n64dev.org/ for background on the CIC chips.
+
+use crate::RandomizerError;
+
+const HEADER_SIZE: usize = 0x40;
+const BOOT_CODE_END: usize = 0x1000;
+
+pub const CRC1_OFFSET: u32 = 0x10;
+pub const CRC2_OFFSET: u32 = 0x14;
+
+const CHECKSUM_START: usize = 0x1000;
+const CHECKSUM_LENGTH: usize = 0x0010_0000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cic {
+    Cic6101,
+    Cic6102,
+    Cic6103,
+    Cic6105,
+    Cic6106,
+}
+
+impl Cic {
+    fn seed(self) -> u32 {
+        use Cic::*;
+        match self {
+            Cic6101 | Cic6102 => 0xF8CA_4DDC,
+            Cic6103 => 0xA388_6759,
+            Cic6105 => 0xDF26_F436,
+            Cic6106 => 0x1FEA_617A,
+        }
+    }
+}
+
+/// The standard (zlib/PNG) CRC-32.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Identify the CIC chip the ROM expects by hashing its boot code, the same way rn64crc does.
+/// Unlike rn64crc, we don't guess if the boot code is unrecognized.
+pub fn detect_cic(rom: &[u8]) -> Result<Cic, RandomizerError> {
+    let boot_code = rom
+        .get(HEADER_SIZE..BOOT_CODE_END)
+        .ok_or_else(|| RandomizerError::WrongRom(
+            format!("rom is too short ({} bytes) to contain boot code", rom.len())
+        ))?;
+
+    use Cic::*;
+    match crc32(boot_code) {
+        0x6170_A4A1 => Ok(Cic6101),
+        0x90BB_6CB5 => Ok(Cic6102),
+        0x0B05_0EE0 => Ok(Cic6103),
+        0x98BC_2C86 => Ok(Cic6105),
+        0xACC8_580A => Ok(Cic6106),
+        crc => Err(RandomizerError::WrongRom(
+            format!("unknown CIC boot code (boot code crc32 {:#010x})", crc)
+        )),
+    }
+}
+
+fn read_u32(rom: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([rom[offset], rom[offset + 1], rom[offset + 2], rom[offset + 3]])
+}
+
+/// Calculate the two header checksum words, which belong at `CRC1_OFFSET` and `CRC2_OFFSET`.
+pub fn calculate(rom: &[u8], cic: Cic) -> Result<[u32; 2], RandomizerError> {
+    let end = CHECKSUM_START + CHECKSUM_LENGTH;
+    if rom.len() < end {
+        return Err(RandomizerError::Checksum(format!(
+            "rom is too short ({} bytes) to checksum, need at least {} bytes",
+            rom.len(),
+            end
+        )));
+    }
+
+    let seed = cic.seed();
+    let (mut t1, mut t2, mut t3, mut t4, mut t5, mut t6) = (seed, seed, seed, seed, seed, seed);
+
+    for i in (CHECKSUM_START..end).step_by(4) {
+        let d = read_u32(rom, i);
+        if t6.wrapping_add(d) < t6 {
+            t4 = t4.wrapping_add(1);
+        }
+        t6 = t6.wrapping_add(d);
+        t3 ^= d;
+        let r = d.rotate_left(d & 0x1F);
+        t5 = t5.wrapping_add(r);
+        if t2 > d {
+            t2 ^= r;
+        } else {
+            t2 ^= t6 ^ d;
+        }
+
+        if cic == Cic::Cic6105 {
+            t1 = t1.wrapping_add(read_u32(rom, HEADER_SIZE + 0x0710 + (i & 0xFF)) ^ d);
+        } else {
+            t1 = t1.wrapping_add(t5 ^ d);
+        }
+    }
+
+    Ok(match cic {
+        Cic::Cic6103 => [(t6 ^ t4).wrapping_add(t3), (t5 ^ t2).wrapping_add(t1)],
+        Cic::Cic6106 => [
+            t6.wrapping_mul(t4).wrapping_add(t3),
+            t5.wrapping_mul(t2).wrapping_add(t1),
+        ],
+        Cic::Cic6101 | Cic::Cic6102 | Cic::Cic6105 => [t6 ^ t4 ^ t3, t5 ^ t2 ^ t1],
+    })
+}
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn unknown_boot_code_is_rejected() {
+    let rom = vec![0u8; BOOT_CODE_END];
+    assert!(detect_cic(&rom).is_err());
+}
+
+#[test]
+fn checksums_match_known_values() {
+    let mut rom = vec![0u8; CHECKSUM_START + CHECKSUM_LENGTH];
+    // With nothing but zeroes every running total stays at the seed, except `t1`, which gains
+    // the seed once per word, so CRC1 is the seed and CRC2 is the seed times 0x40001.
+    assert_eq!(calculate(&rom, Cic::Cic6102).unwrap(), [0xF8CA_4DDC, 0x303A_4DDC]);
+
+    // These were worked out with a separate implementation of the same algorithm.
+    for (i, byte) in rom.iter_mut().enumerate() {
+        *byte = (i * 7 + i / 256) as u8;
+    }
+    assert_eq!(calculate(&rom, Cic::Cic6102).unwrap(), [0xF8CE_4DDC, 0x8D5C_CD0E]);
+    assert_eq!(calculate(&rom, Cic::Cic6103).unwrap(), [0xA394_6759, 0xD858_8D94]);
+    assert_eq!(calculate(&rom, Cic::Cic6105).unwrap(), [0xDF2A_F436, 0xBE3B_A2FE]);
+    assert_eq!(calculate(&rom, Cic::Cic6106).unwrap(), [0x0410_0F9E, 0x217F_1DE2]);
+}
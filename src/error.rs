@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::rom::OutOfRange;
+
+/// Everything that can go wrong while randomizing. The variants up to and including `Io` are
+/// problems with what the user gave us. The rest, apart from `Unreachable`, indicate bugs in
+/// the randomizer, (or a rom we failed to reject.)
+#[derive(Debug)]
+pub enum RandomizerError {
+    /// The command line arguments, or settings, could not be understood.
+    BadArguments(String),
+    /// The input is not a rom we know how to patch.
+    WrongRom(String),
+    /// Reading the input or writing the output failed.
+    Io(std::io::Error),
+    /// A hard-coded offset, or a pointer read from the rom, pointed outside the rom.
+    OutOfRange(OutOfRange),
+    /// `roomdata.json` could not be parsed, or does not describe a room from the rom.
+    MissingRoomData(String),
+    /// The header checksum could not be calculated.
+    Checksum(String),
+    /// The rooms or key items could not be arranged so that everything that needs to be
+    /// reachable is.
+    Unreachable(String),
+    /// The randomizer got into a state that should not be possible.
+    Internal(String),
+}
+
+impl RandomizerError {
+    /// These are part of the command line interface, so they should not be changed.
+    pub fn exit_code(&self) -> i32 {
+        use RandomizerError::*;
+        match self {
+            BadArguments(_) => 1,
+            WrongRom(_) => 2,
+            Io(_) => 3,
+            OutOfRange(_) => 4,
+            MissingRoomData(_) => 5,
+            Checksum(_) => 6,
+            Unreachable(_) => 7,
+            Internal(_) => 8,
+        }
+    }
+
+    /// A description of each exit code, in order, suitable for `--help` output.
+    pub fn exit_code_descriptions() -> [(i32, &'static str); 8] {
+        [
+            (1, "bad arguments"),
+            (2, "the input is not a rom that can be randomized"),
+            (3, "could not read the input or write the output"),
+            (4, "internal error: an offset was outside the rom"),
+            (5, "internal error: room data is missing or invalid"),
+            (6, "internal error: could not calculate the checksum"),
+            (7, "could not arrange the rooms and key items so everything can be reached, try another seed"),
+            (8, "internal error: the randomizer got into a state that should not be possible"),
+        ]
+    }
+}
+
+impl fmt::Display for RandomizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use RandomizerError::*;
+        match self {
+            BadArguments(message) => write!(f, "{}", message),
+            WrongRom(message) => write!(f, "{}", message),
+            Io(error) => write!(f, "{}", error),
+            OutOfRange(error) => write!(f, "{}", error),
+            MissingRoomData(message) => write!(f, "room data error: {}", message),
+            Checksum(message) => write!(f, "checksum error: {}", message),
+            Unreachable(message) => write!(f, "{}", message),
+            Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RandomizerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RandomizerError::Io(error) => Some(error),
+            RandomizerError::OutOfRange(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RandomizerError {
+    fn from(error: std::io::Error) -> Self {
+        RandomizerError::Io(error)
+    }
+}
+
+impl From<OutOfRange> for RandomizerError {
+    fn from(error: OutOfRange) -> Self {
+        RandomizerError::OutOfRange(error)
+    }
+}
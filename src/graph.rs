@@ -0,0 +1,329 @@
+// Which rooms can be reached from which, and fixing up randomized warps so that the rooms that
+// matter stay reachable.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::rooms::{coupled_targets, Door, Targets, Warp};
+use crate::xs::{xs_choice, xs_choice_str, xs_shuffle, Xs};
+use crate::{get_room, RandomizerError, RoomData};
+
+/// Where the player first appears, when starting a new file.
+pub(crate) const START_ROOM: &str = "kmr_00";
+/// The warps out of `START_ROOM`, which are pointed somewhere else to start the player there.
+pub(crate) const OPENING_WARPS: [u32; 2] = [2149846604, 2149854336];
+
+/// The rooms each room has a warp into.
+pub(crate) type Graph = HashMap<&'static str, Vec<&'static str>>;
+
+/// The graph of `warps`, where each warp goes to its target in `targets` if it has one, or
+/// where it goes in the unmodified rom otherwise.
+pub(crate) fn build(warps: &[Warp], targets: &Targets) -> Graph {
+    let mut graph = Graph::with_capacity(warps.len());
+    for warp in warps.iter() {
+        let target_room = targets.get(&(warp.room, warp.warp_ptr))
+            .map_or(warp.target_room, |&(room, _)| room);
+        graph.entry(warp.room).or_default().push(target_room);
+        graph.entry(target_room).or_default();
+    }
+    graph
+}
+
+fn reverse(graph: &Graph) -> Graph {
+    let mut reversed = Graph::with_capacity(graph.len());
+    for (&room, targets) in graph.iter() {
+        reversed.entry(room).or_default();
+        for &target in targets.iter() {
+            reversed.entry(target).or_default().push(room);
+        }
+    }
+    reversed
+}
+
+/// Every room that can be reached from `from`, including `from` itself.
+pub(crate) fn reachable_from(graph: &Graph, from: &str) -> HashSet<&'static str> {
+    let mut reached = HashSet::with_capacity(graph.len());
+    let mut queue = VecDeque::new();
+    if let Some((&from, _)) = graph.get_key_value(from) {
+        reached.insert(from);
+        queue.push_back(from);
+    }
+    while let Some(room) = queue.pop_front() {
+        for &target in graph[room].iter() {
+            if reached.insert(target) {
+                queue.push_back(target);
+            }
+        }
+    }
+    reached
+}
+
+/// The rooms which randomizing must not cut off: the largest group of rooms in `allowed` which
+/// can all be reached from each other in the unmodified rom. Rooms outside that group are only
+/// reachable in ways the warps do not describe, (cutscenes, mostly,) or not at all.
+pub(crate) fn required_rooms(vanilla: &Graph, allowed: &HashSet<&str>) -> Vec<&'static str> {
+    let graph: Graph = vanilla.iter()
+        .filter(|(room, _)| allowed.contains(*room))
+        .map(|(&room, targets)| {
+            (room, targets.iter().copied().filter(|t| allowed.contains(t)).collect())
+        })
+        .collect();
+    let reversed = reverse(&graph);
+
+    let mut rooms: Vec<&'static str> = graph.keys().copied().collect();
+    rooms.sort();
+
+    let mut grouped = HashSet::with_capacity(rooms.len());
+    let mut largest = Vec::new();
+    for &room in rooms.iter() {
+        if grouped.contains(room) {
+            continue;
+        }
+        let backwards = reachable_from(&reversed, room);
+        let mut group: Vec<_> = reachable_from(&graph, room)
+            .into_iter()
+            .filter(|r| backwards.contains(r))
+            .collect();
+        grouped.extend(group.iter().copied());
+        if group.len() > largest.len() {
+            group.sort();
+            largest = group;
+        }
+    }
+
+    largest
+}
+
+/// How close `graph` is to being completable. The required rooms should all be reachable from
+/// the start, and from each other, which we check by going through the first one of them.
+struct Score {
+    from_start: HashSet<&'static str>,
+    from_first: HashSet<&'static str>,
+    to_first: HashSet<&'static str>,
+}
+
+impl Score {
+    fn new(graph: &Graph, start: &str, required: &[&'static str]) -> Self {
+        let first = required.first().copied().unwrap_or(start);
+        Score {
+            from_start: reachable_from(graph, start),
+            from_first: reachable_from(graph, first),
+            to_first: reachable_from(&reverse(graph), first),
+        }
+    }
+
+    fn value(&self, required: &[&'static str]) -> usize {
+        required.iter()
+            .map(|r| {
+                self.from_start.contains(r) as usize
+                    + self.from_first.contains(r) as usize
+                    + self.to_first.contains(r) as usize
+            })
+            .sum()
+    }
+
+    fn is_complete(&self, required: &[&'static str]) -> bool {
+        self.value(required) == required.len() * 3
+    }
+}
+
+/// Make sure every required room can be reached, by redirecting randomly chosen warps in
+/// `targets` other than the ones in `fixed`, into rooms that cannot be reached yet. A warp is
+/// only redirected into a room if `can_target` says it may lead there. Returns how many warps
+/// were redirected.
+pub(crate) fn repair_targets(
+    xs: &mut Xs,
+    warps: &[Warp],
+    targets: &mut Targets,
+    fixed: &HashSet<(&'static str, u32)>,
+    can_target: &dyn Fn((&'static str, u32), &str) -> bool,
+    room_data: &RoomData,
+    required: &[&'static str],
+) -> Result<usize, RandomizerError> {
+    let mut candidates: Vec<(&'static str, u32)> = warps.iter()
+        .map(|warp| (warp.room, warp.warp_ptr))
+        .filter(|key| targets.contains_key(key) && !fixed.contains(key))
+        .collect();
+    candidates.sort();
+
+    let mut repairs = 0;
+    loop {
+        let score = Score::new(&build(warps, targets), START_ROOM, required);
+        if score.is_complete(required) {
+            return Ok(repairs);
+        }
+        let value = score.value(required);
+
+        // Each attempt sends a warp from a room in `from` to a room in `to`.
+        let mut attempts: Vec<(&HashSet<&'static str>, &'static str)> = Vec::new();
+        let first_to_rooms: Vec<&'static str> = required.iter()
+            .copied()
+            .filter(|r| score.to_first.contains(r))
+            .collect();
+        let mut from_missing = Vec::new();
+        for &room in required.iter() {
+            if !score.from_start.contains(room) {
+                attempts.push((&score.from_start, room));
+            }
+            if !score.from_first.contains(room) {
+                attempts.push((&score.from_first, room));
+            }
+            if !score.to_first.contains(room) && !first_to_rooms.is_empty() {
+                from_missing.push(room);
+            }
+        }
+        let graph = build(warps, targets);
+        let from_missing: Vec<_> = from_missing.iter()
+            .map(|&room| (reachable_from(&graph, room), xs_choice_str(xs, &first_to_rooms)))
+            .collect();
+        attempts.extend(from_missing.iter().map(|(from, to)| (from, *to)));
+
+        let mut improved = false;
+        'attempts: for (from, to) in attempts {
+            let mut order: Vec<u32> = (0..candidates.len() as u32).collect();
+            xs_shuffle(xs, &mut order);
+            let entrance = xs_choice(xs, &get_room(room_data, to)?.entrances);
+
+            for &i in order.iter() {
+                let key = candidates[i as usize];
+                if !from.contains(key.0) || !can_target(key, to) {
+                    continue;
+                }
+                let old = targets.insert(key, (to, entrance));
+                let new_score = Score::new(&build(warps, targets), START_ROOM, required);
+                if new_score.value(required) > value {
+                    improved = true;
+                    break 'attempts;
+                }
+                match old {
+                    Some(old) => targets.insert(key, old),
+                    None => targets.remove(&key),
+                };
+            }
+        }
+
+        if !improved {
+            return Err(unreachable_error(&score, required));
+        }
+        repairs += 1;
+    }
+}
+
+/// Make sure every required room can be reached, by swapping which doors are joined together
+/// in `pairs`. Joining a reachable pair of doors to an unreachable one, crosswise, keeps both
+/// reachable doors connected to each other through the unreachable pair, so this only fails
+/// when there is no pair left to join to. Only pairs with the same entry in `groups` are
+/// joined. Warps in `fixed` go where it says, whatever door they are paired with. Returns how
+/// many swaps were made.
+pub(crate) fn repair_pairs(
+    xs: &mut Xs,
+    warps: &[Warp],
+    pairs: &mut [(Door, Door)],
+    groups: &[&'static str],
+    fixed: &Targets,
+    required: &[&'static str],
+) -> Result<usize, RandomizerError> {
+    let targets = |pairs: &[(Door, Door)]| {
+        let mut targets = coupled_targets(pairs);
+        targets.extend(fixed.iter());
+        targets
+    };
+    let mut repairs = 0;
+    loop {
+        let score = Score::new(&build(warps, &targets(pairs)), START_ROOM, required);
+        if score.is_complete(required) {
+            return Ok(repairs);
+        }
+        let value = score.value(required);
+
+        let reached = |door: &Door| score.from_start.contains(door.room);
+        let reachable: Vec<u32> = (0..pairs.len() as u32)
+            .filter(|&i| reached(&pairs[i as usize].0) && reached(&pairs[i as usize].1))
+            .collect();
+        let mut unreachable: Vec<u32> = (0..pairs.len() as u32)
+            .filter(|&i| !reached(&pairs[i as usize].0) || !reached(&pairs[i as usize].1))
+            .collect();
+        xs_shuffle(xs, &mut unreachable);
+
+        let mut improved = false;
+        'pairs: for &u in unreachable.iter() {
+            let u = u as usize;
+            let reachable: Vec<u32> = reachable.iter()
+                .copied()
+                .filter(|&r| groups[r as usize] == groups[u])
+                .collect();
+            if reachable.is_empty() {
+                continue;
+            }
+            let r = xs_choice(xs, &reachable) as usize;
+            let (a, b) = pairs[r];
+            let (c, d) = pairs[u];
+            for &(first, second) in [((a, c), (b, d)), ((a, d), (b, c))].iter() {
+                pairs[r] = first;
+                pairs[u] = second;
+                let new_score = Score::new(&build(warps, &targets(pairs)), START_ROOM, required);
+                if new_score.value(required) > value {
+                    improved = true;
+                    break 'pairs;
+                }
+            }
+            pairs[r] = (a, b);
+            pairs[u] = (c, d);
+        }
+
+        if !improved {
+            return Err(unreachable_error(&score, required));
+        }
+        repairs += 1;
+    }
+}
+
+fn unreachable_error(score: &Score, required: &[&'static str]) -> RandomizerError {
+    let missing: Vec<&str> = required.iter()
+        .copied()
+        .filter(|r| !score.from_start.contains(r))
+        .collect();
+    RandomizerError::Unreachable(format!(
+        "could not connect every room. Unreachable from {}: {:?}",
+        START_ROOM,
+        missing
+    ))
+}
+
+#[test]
+fn repaired_targets_reach_every_required_room() {
+    let names = ["kmr_00", "aaa_00", "aaa_01", "aaa_02", "aaa_03"];
+    let mut room_data = RoomData::new();
+    let mut warps = Vec::new();
+    for (i, &room) in names.iter().enumerate() {
+        let warp_ptrs = vec![1, 2];
+        for &warp_ptr in warp_ptrs.iter() {
+            warps.push(Warp {
+                room,
+                warp_ptr,
+                index: warp_ptr as usize - 1,
+                target_room: names[(i + warp_ptr as usize) % names.len()],
+                target_entrance: 0,
+            });
+        }
+        room_data.insert(room, crate::Room { entrances: vec![0], items: vec![], item_needs: vec![], warp_ptrs });
+    }
+    let allowed: HashSet<&str> = names.iter().copied().collect();
+    let required = required_rooms(&build(&warps, &Targets::new()), &allowed);
+    assert_eq!(required.len(), names.len());
+
+    for seed in 1..20 {
+        let xs = &mut crate::xs::xs_from_seed(seed, crate::RngVersion::Uniform);
+        // everything leads into one room, except the start.
+        let mut targets: Targets = warps.iter()
+            .map(|warp| ((warp.room, warp.warp_ptr), ("aaa_00", 0)))
+            .collect();
+        targets.insert(("kmr_00", 1), ("aaa_01", 0));
+        let fixed = std::iter::once(("kmr_00", 1)).collect();
+
+        repair_targets(xs, &warps, &mut targets, &fixed, &|_, _| true, &room_data, &required).unwrap();
+
+        assert_eq!(targets[&("kmr_00", 1)], ("aaa_01", 0));
+        let graph = build(&warps, &targets);
+        assert!(Score::new(&graph, START_ROOM, &required).is_complete(&required));
+    }
+}
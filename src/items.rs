@@ -0,0 +1,443 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ItemCategory {
+    Consumable,
+    /// Includes equipment like the boots and hammers.
+    KeyItem,
+    Badge,
+    StarPiece,
+    /// Ids which have no item in the game, or only a left over one.
+    Unused,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Item {
+    pub id: u32,
+    pub name: &'static str,
+    pub category: ItemCategory,
+    /// In coins. Zero for things that cannot be sold.
+    pub sell_price: u16,
+    /// Only badges have a BP cost.
+    pub bp_cost: Option<u8>,
+}
+
+const fn item(
+    id: u32,
+    name: &'static str,
+    category: ItemCategory,
+    sell_price: u16,
+    bp_cost: Option<u8>,
+) -> Item {
+    Item { id, name, category, sell_price, bp_cost }
+}
+
+use ItemCategory::*;
+
+// Badge ids and names are from http://shrines.rpgclassics.com/n64/papermario/hacking.shtml
+// endian swapped, like the rest of the ids. Sell prices and BP costs were collected by hand and
+// have not all been checked against the item table in the rom. Badges cannot be sold, so their
+// sell price is zero.
+//
+// The ids between the Blue Key and the Fire Flower are key items (letters, cake ingredients,
+// and so on) which have not been individually identified yet, so they are named by their id.
+const ITEMS: [Item; 0x16B] = [
+    item(0x001, "Boots", KeyItem, 0, None),
+    item(0x002, "Super Boots", KeyItem, 0, None),
+    item(0x003, "Ultra Boots", KeyItem, 0, None),
+    item(0x004, "Hammer", KeyItem, 0, None),
+    item(0x005, "Super Hammer", KeyItem, 0, None),
+    item(0x006, "Ultra Hammer", KeyItem, 0, None),
+    item(0x007, "Lucky Star", KeyItem, 0, None),
+    item(0x008, "Map", KeyItem, 0, None),
+    item(0x009, "Big Map", KeyItem, 0, None),
+    item(0x00A, "First Degree Card", KeyItem, 0, None),
+    item(0x00B, "Second Degree Card", KeyItem, 0, None),
+    item(0x00C, "Third Degree Card", KeyItem, 0, None),
+    item(0x00D, "Fourth Degree Card", KeyItem, 0, None),
+    item(0x00E, "Diploma", KeyItem, 0, None),
+    item(0x00F, "Ultra Stone", KeyItem, 0, None),
+    item(0x010, "Fortress Key", KeyItem, 0, None),
+    item(0x011, "Ruins Key", KeyItem, 0, None),
+    item(0x012, "Pulse Stone", KeyItem, 0, None),
+    item(0x013, "Tubba Castle Key", KeyItem, 0, None),
+    item(0x014, "Crystal Palace Key", KeyItem, 0, None),
+    item(0x015, "Lunar Stone", KeyItem, 0, None),
+    item(0x016, "Pyramid Stone", KeyItem, 0, None),
+    item(0x017, "Diamond Stone", KeyItem, 0, None),
+    item(0x018, "Golden Vase", KeyItem, 0, None),
+    item(0x019, "Kooper's Shell", KeyItem, 0, None),
+    item(0x01A, "Bowser's Castle Key", KeyItem, 0, None),
+    item(0x01B, "Forest Pass", KeyItem, 0, None),
+    item(0x01C, "Weight", KeyItem, 0, None),
+    item(0x01D, "Boo's Portrait", KeyItem, 0, None),
+    item(0x01E, "Crystal Berry", KeyItem, 0, None),
+    item(0x01F, "Mystical Key", KeyItem, 0, None),
+    item(0x020, "Storeroom Key", KeyItem, 0, None),
+    item(0x021, "Toy Train", KeyItem, 0, None),
+    item(0x022, "Ring", KeyItem, 0, None),
+    item(0x023, "Frying Pan", KeyItem, 0, None),
+    item(0x024, "Dictionary", KeyItem, 0, None),
+    item(0x025, "Mystery Note", KeyItem, 0, None),
+    item(0x026, "Suspicious Note", KeyItem, 0, None),
+    item(0x027, "Crystal Ball", KeyItem, 0, None),
+    item(0x028, "Screwdriver", KeyItem, 0, None),
+    item(0x029, "Cookbook", KeyItem, 0, None),
+    item(0x02A, "Jade Raven", KeyItem, 0, None),
+    item(0x02B, "Magical Seed (red)", KeyItem, 0, None),
+    item(0x02C, "Magical Seed (purple)", KeyItem, 0, None),
+    item(0x02D, "Magical Seed (blue)", KeyItem, 0, None),
+    item(0x02E, "Magical Seed (green)", KeyItem, 0, None),
+    item(0x02F, "Toad Doll", KeyItem, 0, None),
+    item(0x030, "Calculator", KeyItem, 0, None),
+    item(0x031, "Bucket", KeyItem, 0, None),
+    item(0x032, "Scarf", KeyItem, 0, None),
+    item(0x033, "Red Key", KeyItem, 0, None),
+    item(0x034, "Blue Key", KeyItem, 0, None),
+    item(0x035, "Key Item 0x35", KeyItem, 0, None),
+    item(0x036, "Key Item 0x36", KeyItem, 0, None),
+    item(0x037, "Key Item 0x37", KeyItem, 0, None),
+    item(0x038, "Key Item 0x38", KeyItem, 0, None),
+    item(0x039, "Key Item 0x39", KeyItem, 0, None),
+    item(0x03A, "Key Item 0x3a", KeyItem, 0, None),
+    item(0x03B, "Key Item 0x3b", KeyItem, 0, None),
+    item(0x03C, "Key Item 0x3c", KeyItem, 0, None),
+    item(0x03D, "Key Item 0x3d", KeyItem, 0, None),
+    item(0x03E, "Key Item 0x3e", KeyItem, 0, None),
+    item(0x03F, "Key Item 0x3f", KeyItem, 0, None),
+    item(0x040, "Key Item 0x40", KeyItem, 0, None),
+    item(0x041, "Key Item 0x41", KeyItem, 0, None),
+    item(0x042, "Key Item 0x42", KeyItem, 0, None),
+    item(0x043, "Key Item 0x43", KeyItem, 0, None),
+    item(0x044, "Key Item 0x44", KeyItem, 0, None),
+    item(0x045, "Key Item 0x45", KeyItem, 0, None),
+    item(0x046, "Key Item 0x46", KeyItem, 0, None),
+    item(0x047, "Key Item 0x47", KeyItem, 0, None),
+    item(0x048, "Key Item 0x48", KeyItem, 0, None),
+    item(0x049, "Key Item 0x49", KeyItem, 0, None),
+    item(0x04A, "Key Item 0x4a", KeyItem, 0, None),
+    item(0x04B, "Key Item 0x4b", KeyItem, 0, None),
+    item(0x04C, "Key Item 0x4c", KeyItem, 0, None),
+    item(0x04D, "Key Item 0x4d", KeyItem, 0, None),
+    item(0x04E, "Key Item 0x4e", KeyItem, 0, None),
+    item(0x04F, "Key Item 0x4f", KeyItem, 0, None),
+    item(0x050, "Key Item 0x50", KeyItem, 0, None),
+    item(0x051, "Key Item 0x51", KeyItem, 0, None),
+    item(0x052, "Key Item 0x52", KeyItem, 0, None),
+    item(0x053, "Key Item 0x53", KeyItem, 0, None),
+    item(0x054, "Key Item 0x54", KeyItem, 0, None),
+    item(0x055, "Key Item 0x55", KeyItem, 0, None),
+    item(0x056, "Key Item 0x56", KeyItem, 0, None),
+    item(0x057, "Key Item 0x57", KeyItem, 0, None),
+    item(0x058, "Key Item 0x58", KeyItem, 0, None),
+    item(0x059, "Key Item 0x59", KeyItem, 0, None),
+    item(0x05A, "Key Item 0x5a", KeyItem, 0, None),
+    item(0x05B, "Key Item 0x5b", KeyItem, 0, None),
+    item(0x05C, "Key Item 0x5c", KeyItem, 0, None),
+    item(0x05D, "Key Item 0x5d", KeyItem, 0, None),
+    item(0x05E, "Key Item 0x5e", KeyItem, 0, None),
+    item(0x05F, "Key Item 0x5f", KeyItem, 0, None),
+    item(0x060, "Key Item 0x60", KeyItem, 0, None),
+    item(0x061, "Key Item 0x61", KeyItem, 0, None),
+    item(0x062, "Key Item 0x62", KeyItem, 0, None),
+    item(0x063, "Key Item 0x63", KeyItem, 0, None),
+    item(0x064, "Key Item 0x64", KeyItem, 0, None),
+    item(0x065, "Key Item 0x65", KeyItem, 0, None),
+    item(0x066, "Key Item 0x66", KeyItem, 0, None),
+    item(0x067, "Key Item 0x67", KeyItem, 0, None),
+    item(0x068, "Key Item 0x68", KeyItem, 0, None),
+    item(0x069, "Key Item 0x69", KeyItem, 0, None),
+    item(0x06A, "Key Item 0x6a", KeyItem, 0, None),
+    item(0x06B, "Key Item 0x6b", KeyItem, 0, None),
+    item(0x06C, "Key Item 0x6c", KeyItem, 0, None),
+    item(0x06D, "Key Item 0x6d", KeyItem, 0, None),
+    item(0x06E, "Key Item 0x6e", KeyItem, 0, None),
+    item(0x06F, "Key Item 0x6f", KeyItem, 0, None),
+    item(0x070, "Key Item 0x70", KeyItem, 0, None),
+    item(0x071, "Key Item 0x71", KeyItem, 0, None),
+    item(0x072, "Key Item 0x72", KeyItem, 0, None),
+    item(0x073, "Key Item 0x73", KeyItem, 0, None),
+    item(0x074, "Key Item 0x74", KeyItem, 0, None),
+    item(0x075, "Key Item 0x75", KeyItem, 0, None),
+    item(0x076, "Key Item 0x76", KeyItem, 0, None),
+    item(0x077, "Key Item 0x77", KeyItem, 0, None),
+    item(0x078, "Key Item 0x78", KeyItem, 0, None),
+    item(0x079, "Key Item 0x79", KeyItem, 0, None),
+    item(0x07A, "Key Item 0x7a", KeyItem, 0, None),
+    item(0x07B, "Key Item 0x7b", KeyItem, 0, None),
+    item(0x07C, "Key Item 0x7c", KeyItem, 0, None),
+    item(0x07D, "Key Item 0x7d", KeyItem, 0, None),
+    item(0x07E, "Key Item 0x7e", KeyItem, 0, None),
+    item(0x07F, "Key Item 0x7f", KeyItem, 0, None),
+    item(0x080, "Fire Flower", Consumable, 5, None),
+    item(0x081, "Snowman Doll", Consumable, 6, None),
+    item(0x082, "Thunder Rage", Consumable, 7, None),
+    item(0x083, "Shooting Star", Consumable, 15, None),
+    item(0x084, "Thunder Bolt", Consumable, 2, None),
+    item(0x085, "Pebble", Consumable, 1, None),
+    item(0x086, "Dusty Hammer", Consumable, 1, None),
+    item(0x087, "Insecticide Herb", Consumable, 3, None),
+    item(0x088, "Stone Cap", Consumable, 15, None),
+    item(0x089, "Tasty Tonic", Consumable, 1, None),
+    item(0x08A, "Mushroom", Consumable, 2, None),
+    item(0x08B, "Volt Shroom", Consumable, 5, None),
+    item(0x08C, "Super Shroom", Consumable, 7, None),
+    item(0x08D, "Dried Shroom", Consumable, 1, None),
+    item(0x08E, "Ultra Shroom", Consumable, 15, None),
+    item(0x08F, "Sleepy Sheep", Consumable, 5, None),
+    item(0x090, "POW Block", Consumable, 7, None),
+    item(0x091, "Hustle Drink", Consumable, 7, None),
+    item(0x092, "Stop Watch", Consumable, 12, None),
+    item(0x093, "Whacka's Bump", Consumable, 12, None),
+    item(0x094, "Apple", Consumable, 2, None),
+    item(0x095, "Life Shroom", Consumable, 20, None),
+    item(0x096, "Mystery", Consumable, 0, None),
+    item(0x097, "Repel Gel", Consumable, 7, None),
+    item(0x098, "Fright Jar", Consumable, 2, None),
+    item(0x099, "Please Come Back", Consumable, 2, None),
+    item(0x09A, "Dizzy Dial", Consumable, 10, None),
+    item(0x09B, "Super Soda", Consumable, 6, None),
+    item(0x09C, "Lemon", Consumable, 2, None),
+    item(0x09D, "Lime", Consumable, 2, None),
+    item(0x09E, "Blue Berry", Consumable, 2, None),
+    item(0x09F, "Red Berry", Consumable, 2, None),
+    item(0x0A0, "Yellow Berry", Consumable, 2, None),
+    item(0x0A1, "Bubble Berry", Consumable, 2, None),
+    item(0x0A2, "Jammin' Jelly", Consumable, 12, None),
+    item(0x0A3, "Maple Syrup", Consumable, 5, None),
+    item(0x0A4, "Honey Syrup", Consumable, 2, None),
+    item(0x0A5, "Goomnut", Consumable, 1, None),
+    item(0x0A6, "Koopa Leaf", Consumable, 1, None),
+    item(0x0A7, "Dried Pasta", Consumable, 1, None),
+    item(0x0A8, "Dried Fruit", Consumable, 1, None),
+    item(0x0A9, "Strange Leaf", Consumable, 1, None),
+    item(0x0AA, "Cake Mix", Consumable, 2, None),
+    item(0x0AB, "Egg", Consumable, 2, None),
+    item(0x0AC, "Coconut", Consumable, 1, None),
+    item(0x0AD, "Melon", Consumable, 5, None),
+    item(0x0AE, "Stinky Herb", Consumable, 1, None),
+    item(0x0AF, "Iced Potato", Consumable, 4, None),
+    item(0x0B0, "Spicy Soup", Consumable, 5, None),
+    item(0x0B1, "Apple Pie", Consumable, 5, None),
+    item(0x0B2, "Honey Ultra", Consumable, 25, None),
+    item(0x0B3, "Maple Ultra", Consumable, 25, None),
+    item(0x0B4, "Jelly Ultra", Consumable, 30, None),
+    item(0x0B5, "Koopasta", Consumable, 7, None),
+    item(0x0B6, "Fried Shroom", Consumable, 5, None),
+    item(0x0B7, "Shroom Cake", Consumable, 10, None),
+    item(0x0B8, "Shroom Steak", Consumable, 20, None),
+    item(0x0B9, "Hot Shroom", Consumable, 5, None),
+    item(0x0BA, "Sweet Shroom", Consumable, 20, None),
+    item(0x0BB, "Yummy Meal", Consumable, 25, None),
+    item(0x0BC, "Healthy Juice", Consumable, 20, None),
+    item(0x0BD, "Bland Meal", Consumable, 5, None),
+    item(0x0BE, "Deluxe Feast", Consumable, 30, None),
+    item(0x0BF, "Special Shake", Consumable, 20, None),
+    item(0x0C0, "Big Cookie", Consumable, 10, None),
+    item(0x0C1, "Cake", Consumable, 10, None),
+    item(0x0C2, "Mistake", Consumable, 0, None),
+    item(0x0C3, "Koopa Tea", Consumable, 5, None),
+    item(0x0C4, "Honey Super", Consumable, 10, None),
+    item(0x0C5, "Maple Super", Consumable, 10, None),
+    item(0x0C6, "Jelly Super", Consumable, 15, None),
+    item(0x0C7, "Spaghetti", Consumable, 7, None),
+    item(0x0C8, "Egg Missile", Consumable, 7, None),
+    item(0x0C9, "Fried Egg", Consumable, 3, None),
+    item(0x0CA, "Honey Shroom", Consumable, 5, None),
+    item(0x0CB, "Honey Candy", Consumable, 5, None),
+    item(0x0CC, "Electro Pop", Consumable, 10, None),
+    item(0x0CD, "Fire Pop", Consumable, 5, None),
+    item(0x0CE, "Lime Candy", Consumable, 5, None),
+    item(0x0CF, "Coco Pop", Consumable, 5, None),
+    item(0x0D0, "Lemon Candy", Consumable, 5, None),
+    item(0x0D1, "Jelly Pop", Consumable, 10, None),
+    item(0x0D2, "Strange Cake", Consumable, 7, None),
+    item(0x0D3, "Kooky Cookie", Consumable, 7, None),
+    item(0x0D4, "Frozen Fries", Consumable, 7, None),
+    item(0x0D5, "Potato Salad", Consumable, 5, None),
+    item(0x0D6, "Nutty Cake", Consumable, 5, None),
+    item(0x0D7, "Maple Shroom", Consumable, 10, None),
+    item(0x0D8, "Boiled Egg", Consumable, 3, None),
+    item(0x0D9, "Yoshi Cookie", Consumable, 7, None),
+    item(0x0DA, "Jelly Shroom", Consumable, 10, None),
+    item(0x0DB, "Unused", Unused, 0, None),
+    item(0x0DC, "Unused", Unused, 0, None),
+    item(0x0DD, "Unused", Unused, 0, None),
+    item(0x0DE, "Unused", Unused, 0, None),
+    item(0x0DF, "Unused", Unused, 0, None),
+    item(0x0E0, "Spin Smash", Badge, 0, Some(1)),
+    item(0x0E1, "Multibounce", Badge, 0, Some(1)),
+    item(0x0E2, "Power Plus", Badge, 0, Some(6)),
+    item(0x0E3, "Dodge Master", Badge, 0, Some(2)),
+    item(0x0E4, "Power Bounce", Badge, 0, Some(3)),
+    item(0x0E5, "Spike Shield", Badge, 0, Some(1)),
+    item(0x0E6, "First Attack", Badge, 0, Some(1)),
+    item(0x0E7, "HP Plus", Badge, 0, Some(3)),
+    item(0x0E8, "Quake Hammer", Badge, 0, Some(1)),
+    item(0x0E9, "Double Dip", Badge, 0, Some(1)),
+    item(0x0EA, "Unused", Unused, 0, None),
+    item(0x0EB, "Sleep Stomp", Badge, 0, Some(1)),
+    item(0x0EC, "Fire Shield", Badge, 0, Some(1)),
+    item(0x0ED, "Quick Change", Badge, 0, Some(7)),
+    item(0x0EE, "D-Down Pound", Badge, 0, Some(2)),
+    item(0x0EF, "Dizzy Stomp", Badge, 0, Some(1)),
+    item(0x0F0, "Unused", Unused, 0, None),
+    item(0x0F1, "Pretty Lucky", Badge, 0, Some(2)),
+    item(0x0F2, "Feeling Fine", Badge, 0, Some(3)),
+    item(0x0F3, "Attack FX A", Badge, 0, Some(0)),
+    item(0x0F4, "All or Nothing", Badge, 0, Some(4)),
+    item(0x0F5, "HP Drain", Badge, 0, Some(3)),
+    item(0x0F6, "Unused", Unused, 0, None),
+    item(0x0F7, "Slow Go", Badge, 0, Some(0)),
+    item(0x0F8, "FP Plus", Badge, 0, Some(3)),
+    item(0x0F9, "Mega Rush", Badge, 0, Some(1)),
+    item(0x0FA, "Ice Power", Badge, 0, Some(2)),
+    item(0x0FB, "Defend Plus", Badge, 0, Some(6)),
+    item(0x0FC, "Pay Off", Badge, 0, Some(1)),
+    item(0x0FD, "Money Money", Badge, 0, Some(5)),
+    item(0x0FE, "Chill Out", Badge, 0, Some(1)),
+    item(0x0FF, "Happy Heart", Badge, 0, Some(3)),
+    item(0x100, "Zap Tap", Badge, 0, Some(4)),
+    item(0x101, "Unused", Unused, 0, None),
+    item(0x102, "Right On!", Badge, 0, Some(1)),
+    item(0x103, "Runaway Pay", Badge, 0, Some(2)),
+    item(0x104, "Refund", Badge, 0, Some(1)),
+    item(0x105, "Flower Saver", Badge, 0, Some(4)),
+    item(0x106, "Triple Dip", Badge, 0, Some(3)),
+    item(0x107, "Hammer Throw", Badge, 0, Some(2)),
+    item(0x108, "Mega Quake", Badge, 0, Some(3)),
+    item(0x109, "Smash Charge", Badge, 0, Some(1)),
+    item(0x10A, "Jump Charge", Badge, 0, Some(1)),
+    item(0x10B, "S. Smash Chg.", Badge, 0, Some(2)),
+    item(0x10C, "S. Jump Chg.", Badge, 0, Some(2)),
+    item(0x10D, "Power Rush", Badge, 0, Some(1)),
+    item(0x10E, "Unused", Unused, 0, None),
+    item(0x10F, "Unused", Unused, 0, None),
+    item(0x110, "Unused", Unused, 0, None),
+    item(0x111, "Last Stand", Badge, 0, Some(1)),
+    item(0x112, "Close Call", Badge, 0, Some(1)),
+    item(0x113, "P-Up, D-Down", Badge, 0, Some(2)),
+    item(0x114, "Lucky Day", Badge, 0, Some(7)),
+    item(0x115, "Unused", Unused, 0, None),
+    item(0x116, "P-Down, D-Up", Badge, 0, Some(2)),
+    item(0x117, "Power Quake", Badge, 0, Some(2)),
+    item(0x118, "Unused", Unused, 0, None),
+    item(0x119, "Unused", Unused, 0, None),
+    item(0x11A, "Heart Finder", Badge, 0, Some(3)),
+    item(0x11B, "Flower Finder", Badge, 0, Some(3)),
+    item(0x11C, "Spin Attack", Badge, 0, Some(3)),
+    item(0x11D, "Dizzy Attack", Badge, 0, Some(2)),
+    item(0x11E, "I Spy", Badge, 0, Some(1)),
+    item(0x11F, "Speedy Spin", Badge, 0, Some(1)),
+    item(0x120, "Bump Attack", Badge, 0, Some(5)),
+    item(0x121, "Power Jump", Badge, 0, Some(1)),
+    item(0x122, "Unused", Unused, 0, None),
+    item(0x123, "Mega Jump", Badge, 0, Some(3)),
+    item(0x124, "Power Smash", Badge, 0, Some(1)),
+    item(0x125, "Unused", Unused, 0, None),
+    item(0x126, "Mega Smash", Badge, 0, Some(3)),
+    item(0x127, "Power Smash", Badge, 0, Some(1)),
+    item(0x128, "Power Smash", Badge, 0, Some(1)),
+    item(0x129, "Deep Focus", Badge, 0, Some(1)),
+    item(0x12A, "Unused", Unused, 0, None),
+    item(0x12B, "Shrink Smash", Badge, 0, Some(1)),
+    item(0x12C, "Unused", Unused, 0, None),
+    item(0x12D, "Unused", Unused, 0, None),
+    item(0x12E, "D-Down Jump", Badge, 0, Some(2)),
+    item(0x12F, "Shrink Stomp", Badge, 0, Some(1)),
+    item(0x130, "Damage Dodge", Badge, 0, Some(3)),
+    item(0x131, "Unused", Unused, 0, None),
+    item(0x132, "Deep Focus", Badge, 0, Some(1)),
+    item(0x133, "Deep Focus", Badge, 0, Some(1)),
+    item(0x134, "HP Plus", Badge, 0, Some(3)),
+    item(0x135, "FP Plus", Badge, 0, Some(3)),
+    item(0x136, "Happy Heart", Badge, 0, Some(3)),
+    item(0x137, "Happy Heart", Badge, 0, Some(3)),
+    item(0x138, "Flower Saver", Badge, 0, Some(4)),
+    item(0x139, "Flower Saver", Badge, 0, Some(4)),
+    item(0x13A, "Damage Dodge", Badge, 0, Some(3)),
+    item(0x13B, "Damage Dodge", Badge, 0, Some(3)),
+    item(0x13C, "Power Plus", Badge, 0, Some(6)),
+    item(0x13D, "Power Plus", Badge, 0, Some(6)),
+    item(0x13E, "Defend Plus", Badge, 0, Some(6)),
+    item(0x13F, "Defend Plus", Badge, 0, Some(6)),
+    item(0x140, "Happy Flower", Badge, 0, Some(3)),
+    item(0x141, "Happy Flower", Badge, 0, Some(3)),
+    item(0x142, "Happy Flower", Badge, 0, Some(3)),
+    item(0x143, "Group Focus", Badge, 0, Some(2)),
+    item(0x144, "Peekaboo", Badge, 0, Some(1)),
+    item(0x145, "Attack FX D", Badge, 0, Some(0)),
+    item(0x146, "Attack FX B", Badge, 0, Some(0)),
+    item(0x147, "Attack FX E", Badge, 0, Some(0)),
+    item(0x148, "Attack FX C", Badge, 0, Some(0)),
+    item(0x149, "Attack FX F", Badge, 0, Some(0)),
+    item(0x14A, "HP Plus", Badge, 0, Some(3)),
+    item(0x14B, "HP Plus", Badge, 0, Some(3)),
+    item(0x14C, "HP Plus", Badge, 0, Some(3)),
+    item(0x14D, "FP Plus", Badge, 0, Some(3)),
+    item(0x14E, "FP Plus", Badge, 0, Some(3)),
+    item(0x14F, "FP Plus", Badge, 0, Some(3)),
+    item(0x150, "Unused", Unused, 0, None),
+    item(0x151, "Attack FX F", Badge, 0, Some(0)),
+    item(0x152, "Attack FX F", Badge, 0, Some(0)),
+    item(0x153, "Attack FX F", Badge, 0, Some(0)),
+    item(0x154, "Unused", Unused, 0, None),
+    item(0x155, "Unused", Unused, 0, None),
+    item(0x156, "Unused", Unused, 0, None),
+    item(0x157, "Unused", Unused, 0, None),
+    item(0x158, "Unused", Unused, 0, None),
+    item(0x159, "Unused", Unused, 0, None),
+    item(0x15A, "Unused", Unused, 0, None),
+    item(0x15B, "Unused", Unused, 0, None),
+    item(0x15C, "Unused", Unused, 0, None),
+    item(0x15D, "Unused", Unused, 0, None),
+    item(0x15E, "Unused", Unused, 0, None),
+    item(0x15F, "Star Piece", StarPiece, 0, None),
+    item(0x160, "Unused", Unused, 0, None),
+    item(0x161, "Unused", Unused, 0, None),
+    item(0x162, "Unused", Unused, 0, None),
+    item(0x163, "Unused", Unused, 0, None),
+    item(0x164, "Unused", Unused, 0, None),
+    item(0x165, "Unused", Unused, 0, None),
+    item(0x166, "Unused", Unused, 0, None),
+    item(0x167, "Unused", Unused, 0, None),
+    item(0x168, "Unused", Unused, 0, None),
+    item(0x169, "Unused", Unused, 0, None),
+    item(0x16A, "Unused", Unused, 0, None),
+    item(0x16B, "Unused", Unused, 0, None),
+];
+
+/// Every id from 1 up to, but not including, 0x16C, in order.
+pub fn get_items() -> &'static [Item] {
+    &ITEMS
+}
+
+pub fn get_item(id: u32) -> Option<&'static Item> {
+    ITEMS.get((id as usize).checked_sub(1)?)
+}
+
+/// A name for any id, including ones that are not items.
+pub fn get_item_name(id: u32) -> &'static str {
+    match get_item(id) {
+        Some(item) => item.name,
+        None if id == 0 => "Nothing",
+        None => "Unknown",
+    }
+}
+
+/// The ids of items of the given category, in id order.
+pub fn get_ids_in(category: ItemCategory) -> Vec<u32> {
+    ITEMS
+        .iter()
+        .filter(|item| item.category == category)
+        .map(|item| item.id)
+        .collect()
+}
+
+#[test]
+fn items_are_in_id_order() {
+    for (i, item) in ITEMS.iter().enumerate() {
+        assert_eq!(item.id as usize, i + 1, "{:?}", item);
+        assert_eq!(item.bp_cost.is_some(), item.category == Badge, "{:?}", item);
+    }
+}
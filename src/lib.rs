@@ -0,0 +1,731 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+
+use serde::{Deserialize, Serialize};
+
+pub mod byte_order;
+mod badges;
+use badges::*;
+pub mod checksum;
+mod error;
+pub use error::RandomizerError;
+mod graph;
+mod items;
+pub use items::{get_item, get_item_name, get_items, Item, ItemCategory};
+mod logic;
+pub mod permalink;
+mod rom;
+use rom::Rom;
+mod rooms;
+mod seed_hash;
+pub use seed_hash::{seed_hash, SeedHash};
+mod settings;
+pub use settings::*;
+mod spoiler;
+pub use spoiler::{ItemSpoiler, RoomSpoiler, Spoiler, WarpSpoiler};
+pub mod verify;
+mod warp_graph;
+pub use warp_graph::{WarpEdge, WarpGraph};
+mod xs;
+use xs::*;
+
+pub type Error = RandomizerError;
+
+#[derive(Serialize, Deserialize)]
+struct Room {
+    entrances: Vec<u32>,
+    items: Vec<u32>,
+    /// The ids of the items needed to pick up each of `items`, in the same order. Rooms whose
+    /// items need nothing can leave this out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    item_needs: Vec<Vec<u32>>,
+    warp_ptrs: Vec<u32>,
+}
+
+type RoomData = HashMap<&'static str, Room>;
+
+fn load_room_data() -> Result<RoomData, RandomizerError> {
+    serde_json::from_str(include_str!("roomdata.json"))
+        .map_err(|e| RandomizerError::MissingRoomData(e.to_string()))
+}
+
+/// Every room we know of, in name order, and whether `filter` lets room randomization use it.
+pub fn room_eligibility(filter: &RoomFilter) -> Result<Vec<(&'static str, RoomEligibility)>, Error> {
+    filter.check()?;
+    let mut names: Vec<&'static str> = load_room_data()?.keys().copied().collect();
+    names.sort();
+    Ok(names.into_iter().map(|name| (name, filter.eligibility(name))).collect())
+}
+
+/// Produce a randomized copy of `rom`, which can be in any of the byte orders `byte_order`
+/// knows about. The output is always big-endian.
+pub fn randomize(rom: &[u8], settings: &Settings) -> Result<Vec<u8>, Error> {
+    randomize_with_spoiler(rom, settings).map(|(rom, _)| rom)
+}
+
+/// `randomize`, but also describe the changes that were made.
+pub fn randomize_with_spoiler(rom: &[u8], settings: &Settings) -> Result<(Vec<u8>, Spoiler), Error> {
+    let &Settings {
+        seed,
+        start,
+        ref start_room,
+        item_mode,
+        room_mode,
+        room_scope,
+        warp_rules,
+        shop_prices,
+        ref room_filter,
+        rng_version,
+    } = settings;
+    settings.check()?;
+
+    let mut rom = rom.to_vec();
+    let byte_order = byte_order::detect(&rom)
+        .ok_or_else(|| RandomizerError::WrongRom("input does not look like an N64 rom".to_owned()))?;
+    byte_order::convert(&mut rom, byte_order);
+
+    let original = Rom::new(rom.clone());
+    let mut rom = Rom::new(rom);
+
+    let room_data = load_room_data()?;
+
+    // Rooms with no entrances, like the ending, can never be warped into, whatever the filter
+    // lets through.
+    let mut room_names = room_data.iter()
+        .filter(|(name, room)| room_filter.eligibility(name).is_eligible() && !room.entrances.is_empty())
+        .map(|(&name, _)| name)
+        .collect::<Vec<&str>>();
+    room_names.sort();
+    if room_names.is_empty() {
+        return Err(RandomizerError::BadArguments(
+            "The room filter leaves out every room, so there is no room left to use.".to_owned()
+        ));
+    }
+
+    // make sure this is a paper mario rom
+    if rom.read_bytes(0x20, 11)? != b"PAPER MARIO" {
+        return Err(RandomizerError::WrongRom("Rom type mismatch! The header does not say PAPER MARIO.".to_owned()));
+    }
+
+    // Find out which checksum the rom needs before we change anything, so an unsupported rom
+    // is rejected before we do any work.
+    let cic = checksum::detect_cic(rom.as_bytes())?;
+
+    #[allow(unused_assignments)]
+    {
+        let mut offset = 0x808A8;
+        macro_rules! seek_write_advance {
+            ($data: expr) => {
+                rom.write_u32(offset, $data)?;
+
+                offset += 4;
+            };
+        }
+        // code patch: start with Goombario out
+        seek_write_advance!(0xA0820012);
+        seek_write_advance!(0xA082000A); // enable action command
+        seek_write_advance!(0x2402FFFF);
+        offset = 0x808E4;
+        seek_write_advance!(0xA0800000);
+
+        // have every party member
+        seek_write_advance!(0xA0A20014);
+
+        // enable menus
+        offset = 0x168074;
+        seek_write_advance!(0x2406FF81);
+
+        if let StartMode::Quick = start {
+            // don't start the game from Mario's house
+            offset = 0x168080;
+            seek_write_advance!(0x24020000);
+        }
+    }
+
+    // Set the pointed to exit in the pointed to room at the target room and entrance
+    macro_rules! write_out_room_entrance {
+        (
+            $room_ptr: expr,
+            $warp_ptr: expr,
+            $target_room_name: expr,
+            $target_room_entrance: expr
+        ) => {
+            let room_ptr: u32 = $room_ptr;
+            let warp_ptr: u32 = $warp_ptr;
+            let target_room_name: &str = $target_room_name;
+            let target_room_entrance: u32 = $target_room_entrance;
+            let warp_offset = room_offset(room_ptr, warp_ptr).wrapping_add(0xC);
+            let warp_room_ptr = rom.read_u32(warp_offset)?;
+            rom.write_u32(warp_offset.wrapping_add(4), target_room_entrance)?;
+            let target_room_name = CString::new(target_room_name).map_err(|e|
+                RandomizerError::MissingRoomData(e.to_string())
+            )?;
+            rom.write_bytes(
+                room_offset(room_ptr, warp_room_ptr),
+                target_room_name.as_bytes_with_nul()
+            )?;
+        };
+    }
+
+
+    let mut streams = xs_streams(seed, rng_version);
+
+    // A changed start has to be somewhere the player can get back to the rest of the game from
+    // with the warps of the unmodified rom, since without room randomization nothing repairs a
+    // start which is cut off.
+    let start_rooms = || -> Result<HashSet<&'static str>, RandomizerError> {
+        let warps = rooms::read_vanilla_warps(&original, &room_data)?;
+        let allowed = room_names.iter().copied().collect();
+        Ok(graph::required_rooms(&graph::build(&warps, &rooms::Targets::new()), &allowed).into_iter().collect())
+    };
+
+    // Where leaving the room the game starts in leads, if it is changed.
+    let start_target = match (start_room, room_mode) {
+        (StartRoom::Default, RoomMode::StartWithHammer) => Some(HAMMER_START),
+        (StartRoom::Default, RoomMode::None | RoomMode::TotalRandom | RoomMode::Coupled) => None,
+        (StartRoom::Fixed { room, entrance }, _) => {
+            let (name, data) = rooms::get_room_with_name(&room_data, room).map_err(|_|
+                RandomizerError::BadArguments(format!("There is no room named {:?} to start in.", room))
+            )?;
+            if !data.entrances.contains(entrance) {
+                return Err(RandomizerError::BadArguments(format!(
+                    "{} has no entrance {} to start at. Its entrances are {:?}.",
+                    name, entrance, data.entrances
+                )));
+            }
+            if room_mode == RoomMode::None && !start_rooms()?.contains(name) {
+                return Err(RandomizerError::BadArguments(format!(
+                    "{} is cut off from the rest of the game, so it can only be started in when rooms are randomized.",
+                    name
+                )));
+            }
+            Some((name, *entrance))
+        },
+        (StartRoom::Random, _) => {
+            let required = start_rooms()?;
+            let candidates: Vec<&str> = room_names.iter()
+                .copied()
+                .filter(|&name| name != graph::START_ROOM && required.contains(name))
+                .collect();
+            if candidates.is_empty() {
+                return Err(RandomizerError::BadArguments("Every room is excluded, so there is nowhere to start.".to_owned()));
+            }
+            let room = xs_choice_str(streams.get(Stream::Start), &candidates);
+            let entrance = xs_choice(streams.get(Stream::Start), &get_room(&room_data, room)?.entrances);
+            Some((room, entrance))
+        },
+    };
+
+    #[derive(Debug)]
+    enum ItemState {
+        None,
+        TotalRandom,
+        BadgeDeck(Vec<u32>)
+    }
+
+    let mut item_state = match item_mode {
+        ItemMode::TotalRandom => ItemState::TotalRandom,
+        ItemMode::ShuffleBadgesGlobally => {
+            let mut deck: Vec<u32> = get_map_badges()
+                .into_iter()
+                .chain(get_rowf_shop_badges())
+                .chain(get_merlow_shop_badges())
+                .collect();
+            xs_shuffle(streams.get(Stream::MapItems), &mut deck);
+            ItemState::BadgeDeck(deck)
+        },
+        ItemMode::DealUsedInto(sections) if sections.contains(BadgeSections::Map) => {
+            let mut deck = get_used_badges();
+            xs_shuffle(streams.get(Stream::MapItems), &mut deck);
+            ItemState::BadgeDeck(deck)
+        }
+        ItemMode::DealAllInto(sections) if sections.contains(BadgeSections::Map) => {
+            let mut deck: Vec<u32> = get_badges();
+            xs_shuffle(streams.get(Stream::MapItems), &mut deck);
+            ItemState::BadgeDeck(deck)
+        }
+        ItemMode::ShuffleBadgesLocally(sections) if sections.contains(BadgeSections::Map) => {
+            let mut deck = get_map_badges();
+            xs_shuffle(streams.get(Stream::MapItems), &mut deck);
+            ItemState::BadgeDeck(deck)
+        },
+        ItemMode::None | ItemMode::DealUsedInto(_) | ItemMode::DealAllInto(_) | ItemMode::ShuffleBadgesLocally(_) => ItemState::None,
+    };
+
+    let warps = match room_mode {
+        RoomMode::None => Vec::new(),
+        RoomMode::StartWithHammer | RoomMode::TotalRandom | RoomMode::Coupled =>
+            rooms::read_vanilla_warps(&original, &room_data)?,
+    };
+    let allowed: HashSet<&str> = room_names.iter().copied().collect();
+
+    // The group of each warp that `room_scope` lets us change, and the rooms each group of
+    // warps can lead to.
+    let mut warp_groups = HashMap::with_capacity(warps.len());
+    let mut group_rooms: HashMap<&str, HashSet<&'static str>> = HashMap::new();
+    for warp in warps.iter() {
+        if !allowed.contains(warp.room) || !allowed.contains(warp.target_room) {
+            continue;
+        }
+        if let Some(group) = rooms::warp_group(room_scope, warp.room, warp.target_room) {
+            warp_groups.insert((warp.room, warp.warp_ptr), group);
+            group_rooms.entry(group).or_default().insert(warp.target_room);
+        }
+    }
+    let warp_info = match warp_rules {
+        WarpRules::Ignore => None,
+        WarpRules::Respect => Some(rooms::WarpInfo::new(&warps, &room_data)),
+    };
+    let may_lead_to = |key: (&'static str, u32), room: &str| {
+        warp_info.as_ref().is_none_or(|info| info.may_lead_to(key, room))
+    };
+    let can_target = |key: (&'static str, u32), room: &str| {
+        (room_scope == RoomScope::All
+            || warp_groups.get(&key)
+                .and_then(|group| group_rooms.get(group))
+                .is_some_and(|rooms| rooms.contains(room)))
+            && may_lead_to(key, room)
+    };
+
+    let (mut coupled_pairs, coupled_groups) = match room_mode {
+        RoomMode::Coupled => {
+            let pairs = rooms::pair_warps(&warps, &room_data, &allowed)?;
+            rooms::shuffle_coupled_in_groups(streams.get(Stream::Rooms), &pairs, room_scope)
+        },
+        RoomMode::None | RoomMode::StartWithHammer | RoomMode::TotalRandom => (Vec::new(), Vec::new()),
+    };
+    if let Some(info) = &warp_info {
+        rooms::separate_dead_ends(streams.get(Stream::Rooms), &mut coupled_pairs, &coupled_groups, info);
+    }
+    let mut room_targets = match room_mode {
+        RoomMode::TotalRandom | RoomMode::StartWithHammer if room_scope != RoomScope::All =>
+            rooms::shuffle_targets_in_groups(streams.get(Stream::Rooms), &warps, room_scope, &allowed),
+        RoomMode::None | RoomMode::StartWithHammer | RoomMode::TotalRandom | RoomMode::Coupled =>
+            rooms::Targets::new(),
+    };
+
+    // Key items are placed by `logic::place_items`, so they are left out here to avoid
+    // duplicates.
+    let filler_items: Vec<u32> = get_items()
+        .iter()
+        .filter(|item| item.category != ItemCategory::Unused && item.category != ItemCategory::KeyItem)
+        .map(|item| item.id)
+        .collect();
+    let mut item_locations = Vec::new();
+    let mut seen_item_locations = HashSet::new();
+
+    for i in 0..ROOM_COUNT {
+        let (name, room_ptr) = read_room_table_entry(&rom, i)?;
+        let (name, room) = rooms::get_room_with_name(&room_data, &name)?;
+
+        match room_mode {
+            RoomMode::TotalRandom | RoomMode::StartWithHammer if room_scope == RoomScope::All => {
+                for &warp_ptr in room.warp_ptrs.iter() {
+                    let candidates: Vec<&str>;
+                    let choices = if warp_info.is_some() {
+                        candidates = room_names.iter()
+                            .copied()
+                            .filter(|&target| may_lead_to((name, warp_ptr), target))
+                            .collect();
+                        if candidates.is_empty() {
+                            return Err(RandomizerError::Unreachable(format!(
+                                "no room that warp {:#X} in {} may lead to is left to randomize, so the warp rules cannot be kept",
+                                warp_ptr, name
+                            )));
+                        }
+                        &candidates
+                    } else {
+                        &room_names
+                    };
+                    let rand_room = xs_choice_str(streams.get(Stream::Rooms), choices);
+                    let rand_entrance = xs_choice(streams.get(Stream::Rooms), &get_room(&room_data, rand_room)?.entrances);
+
+                    room_targets.insert((name, warp_ptr), (rand_room, rand_entrance));
+                }
+            },
+            // these were shuffled above, if they are changed at all
+            RoomMode::None | RoomMode::Coupled | RoomMode::TotalRandom | RoomMode::StartWithHammer => {},
+        }
+
+        match &mut item_state {
+            ItemState::None => {}
+            ItemState::BadgeDeck(deck) => {
+                // shuffle room badges
+                let badges_set = get_badges_set();
+                for &item_ptr in room.items.iter() {
+                    let item_offset = room_offset(room_ptr, item_ptr);
+                    let read_u32 = rom.read_u32(item_offset)?;
+                    if badges_set.contains(&read_u32) {
+                        if let Some(item) = deck.pop() {
+                            rom.write_u32(item_offset, item)?;
+                        }
+                    }
+                }
+            }
+            ItemState::TotalRandom => {
+                // these are placed once we know which rooms can be reached
+                for (i, &item_ptr) in room.items.iter().enumerate() {
+                    let item_offset = room_offset(room_ptr, item_ptr);
+                    let read_u32 = rom.read_u32(item_offset)?;
+                    if 0 < read_u32 && read_u32 < 0x200 && seen_item_locations.insert((name, item_ptr)) {
+                        let needs = room.item_needs.get(i).cloned().unwrap_or_default();
+                        item_locations.push(logic::Location { room: name, item_ptr, original: read_u32, needs });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut fixed_targets = rooms::Targets::new();
+    if let Some(target) = start_target {
+        // start there by making "kmr_00" (Mario's fall area) lead there.
+        for &warp_ptr in graph::OPENING_WARPS.iter() {
+            fixed_targets.insert((graph::START_ROOM, warp_ptr), target);
+        }
+    }
+    room_targets.extend(fixed_targets.iter());
+    let fixed_warps: HashSet<_> = fixed_targets.keys().copied().collect();
+
+    // Make sure the player cannot get stuck somewhere, before writing the warps out.
+    if room_mode != RoomMode::None {
+        let required = graph::required_rooms(&graph::build(&warps, &rooms::Targets::new()), &allowed);
+        if let RoomMode::Coupled = room_mode {
+            graph::repair_pairs(streams.get(Stream::Rooms), &warps, &mut coupled_pairs, &coupled_groups, &fixed_targets, &required)?;
+            room_targets = rooms::coupled_targets(&coupled_pairs);
+            room_targets.extend(fixed_targets.iter());
+        } else {
+            // Shuffles within small groups of warps often cannot be repaired a warp at a time,
+            // but a fresh shuffle usually can be.
+            let attempts = if room_scope == RoomScope::All { 1 } else { SCOPED_SHUFFLE_ATTEMPTS };
+            for attempt in 1..=attempts {
+                let result = graph::repair_targets(
+                    streams.get(Stream::Rooms),
+                    &warps,
+                    &mut room_targets,
+                    &fixed_warps,
+                    &can_target,
+                    &room_data,
+                    &required,
+                );
+                // a shuffle that breaks the warp rules is tried again, and reported below if it
+                // was the last one
+                let follows_rules = || room_targets.iter()
+                    .all(|(&key, &(room, _))| fixed_warps.contains(&key) || may_lead_to(key, room));
+                match result {
+                    Ok(_) if attempt == attempts || follows_rules() => break,
+                    Err(error) if attempt == attempts => return Err(error),
+                    Ok(_) | Err(_) => {
+                        room_targets = rooms::shuffle_targets_in_groups(streams.get(Stream::Rooms), &warps, room_scope, &allowed);
+                        room_targets.extend(fixed_targets.iter());
+                    },
+                }
+            }
+        }
+    }
+
+    // A rom which breaks the warp rules could leave the player stuck, so it is not made at all.
+    if let Some(info) = &warp_info {
+        let mut broken: Vec<_> = room_targets.iter()
+            .filter(|&(&key, &(room, _))| !fixed_warps.contains(&key) && !info.may_lead_to(key, room))
+            .collect();
+        broken.sort();
+        if let Some((&(room, warp_ptr), &(target, _))) = broken.first() {
+            return Err(RandomizerError::Unreachable(format!(
+                "could not keep to the warp rules: warp {:#X} in {} leads to {}, and {} other warps break them too. Another seed, or ignoring the warp rules, may work.",
+                warp_ptr, room, target, broken.len() - 1
+            )));
+        }
+    }
+
+    let placed_items = if item_locations.is_empty() {
+        HashMap::new()
+    } else {
+        let reachable_rooms = match room_mode {
+            RoomMode::None => None,
+            RoomMode::StartWithHammer | RoomMode::TotalRandom | RoomMode::Coupled => Some(
+                graph::reachable_from(&graph::build(&warps, &room_targets), graph::START_ROOM)
+            ),
+        };
+        logic::place_items(streams.get(Stream::MapItems), &item_locations, reachable_rooms.as_ref(), &filler_items)?
+    };
+
+    for i in 0..ROOM_COUNT {
+        let (name, room_ptr) = read_room_table_entry(&rom, i)?;
+        let room = get_room(&room_data, &name)?;
+        for &warp_ptr in room.warp_ptrs.iter() {
+            if let Some(&(target_room, target_entrance)) = room_targets.get(&(name.as_str(), warp_ptr)) {
+                write_out_room_entrance!(room_ptr, warp_ptr, target_room, target_entrance);
+            }
+        }
+        for &item_ptr in room.items.iter() {
+            if let Some(&item) = placed_items.get(&(name.as_str(), item_ptr)) {
+                rom.write_u32(room_offset(room_ptr, item_ptr), item)?;
+            }
+        }
+    }
+
+    match item_mode {
+        ItemMode::None => {},
+        ItemMode::TotalRandom => {
+            // TODO if we stuff non-badge item ids in the badge shops here does it work?
+
+            let badges: Vec<_> = get_badges();
+
+            for shop_slot in get_rowf_iter() {
+                let rand_item = xs_choice(streams.get(Stream::Rowf), &badges[..]);
+                rom.write_u32(shop_slot, rand_item)?;
+            }
+
+            for shop_slot in get_merlow_iter() {
+                let rand_item = xs_choice(streams.get(Stream::Merlow), &badges[..]);
+                rom.write_u32(shop_slot, rand_item)?;
+            }
+        },
+        ItemMode::ShuffleBadgesGlobally => {
+            match item_state {
+                ItemState::BadgeDeck(mut deck) => {
+                    for shop_slot in get_rowf_iter() {
+                        if let Some(rand_item) = deck.pop() {
+                            rom.write_u32(shop_slot, rand_item)?;
+                        }
+                    }
+
+                    for shop_slot in get_merlow_iter() {
+                        if let Some(rand_item) = deck.pop() {
+                            rom.write_u32(shop_slot, rand_item)?;
+                        }
+                    }
+                }
+                other => {
+                    return Err(RandomizerError::Internal(format!(
+                        "unexpected state: {:?} but {:?}",
+                        item_mode,
+                        other
+                    )));
+                }
+            }
+        },
+        ItemMode::ShuffleBadgesLocally(sections) => {
+            if sections.contains(BadgeSections::Rowf) {
+                let mut deck = get_rowf_shop_badges();
+                xs_shuffle(streams.get(Stream::Rowf), &mut deck);
+
+                for shop_slot in get_rowf_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+
+            if sections.contains(BadgeSections::Merlow) {
+                let mut deck = get_merlow_shop_badges();
+                xs_shuffle(streams.get(Stream::Merlow), &mut deck);
+
+                for shop_slot in get_merlow_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+        },
+        ItemMode::DealUsedInto(sections) => {
+            if sections.contains(BadgeSections::Rowf) {
+                let mut deck = get_used_badges();
+                xs_shuffle(streams.get(Stream::Rowf), &mut deck);
+
+                for shop_slot in get_rowf_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+
+            if sections.contains(BadgeSections::Merlow) {
+                let mut deck = get_used_badges();
+                xs_shuffle(streams.get(Stream::Merlow), &mut deck);
+
+                for shop_slot in get_merlow_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+        },
+        ItemMode::DealAllInto(sections) => {
+            if sections.contains(BadgeSections::Rowf) {
+                let mut deck: Vec<u32> = get_badges();
+                xs_shuffle(streams.get(Stream::Rowf), &mut deck);
+
+                for shop_slot in get_rowf_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+
+            if sections.contains(BadgeSections::Merlow) {
+                let mut deck: Vec<u32> = get_badges();
+                xs_shuffle(streams.get(Stream::Merlow), &mut deck);
+
+                for shop_slot in get_merlow_iter() {
+                    if let Some(rand_item) = deck.pop() {
+                        rom.write_u32(shop_slot, rand_item)?;
+                    }
+                }
+            }
+        },
+    }
+
+    // The price comes after the item id in each slot.
+    let shops = [(get_rowf_iter(), get_merlow_iter(), true), (get_merlow_iter(), get_rowf_iter(), false)];
+    for (slots, other_slots, is_rowf) in shops {
+        match shop_prices {
+            PriceMode::Keep => {},
+            PriceMode::Carry => {
+                let prices = carried_prices(&original, slots.clone(), other_slots)?;
+                for slot in slots {
+                    if let Some(&price) = prices.get(&rom.read_u32(slot)?) {
+                        rom.write_u32(slot + 4, price)?;
+                    }
+                }
+            },
+            PriceMode::Random { coins, star_pieces } => {
+                let range = if is_rowf { coins } else { star_pieces };
+                for slot in slots {
+                    let price = xs_u32(streams.get(Stream::Prices), range.min.into(), u32::from(range.max) + 1);
+                    rom.write_u32(slot + 4, price)?;
+                }
+            },
+        }
+    }
+
+    let hash = seed_hash(settings);
+    rom.write_bytes(seed_hash::HEADER_OFFSET, hash.code.as_bytes())?;
+
+    let [crc1, crc2] = checksum::calculate(rom.as_bytes(), cic)?;
+    rom.write_u32(checksum::CRC1_OFFSET, crc1)?;
+    rom.write_u32(checksum::CRC2_OFFSET, crc2)?;
+
+    let spoiler = spoiler::read(&original, &rom, &room_data, hash, settings)?;
+
+    Ok((rom.into_bytes(), spoiler))
+}
+
+/// What each badge costs in the shop with `slots` when prices are carried: what that shop charged
+/// for it in `original`. A badge it did not sell costs what the shop with `other_slots` charged,
+/// scaled between the two shops' usual prices, or if neither sold it, the shop's usual price.
+fn carried_prices(
+    original: &Rom,
+    slots: impl Iterator<Item = u32> + Clone,
+    other_slots: impl Iterator<Item = u32> + Clone,
+) -> Result<HashMap<u32, u32>, RandomizerError> {
+    let read_prices = |slots: &mut dyn Iterator<Item = u32>| -> Result<Vec<(u32, u32)>, RandomizerError> {
+        slots.map(|slot| Ok((original.read_u32(slot)?, original.read_u32(slot + 4)?))).collect()
+    };
+    // the middle price, which one odd badge cannot drag around like it could an average
+    let usual_price = |prices: &[(u32, u32)]| {
+        let mut prices: Vec<u32> = prices.iter().map(|&(_, price)| price).collect();
+        prices.sort_unstable();
+        prices.get(prices.len() / 2).copied().unwrap_or(1).max(1)
+    };
+    let sold = read_prices(&mut slots.clone())?;
+    let other_sold = read_prices(&mut other_slots.clone())?;
+    let (usual, other_usual) = (usual_price(&sold), usual_price(&other_sold));
+
+    let mut prices: HashMap<u32, u32> = get_badges().into_iter().map(|id| (id, usual)).collect();
+    for (id, price) in other_sold {
+        let scaled = u64::from(price) * u64::from(usual) / u64::from(other_usual);
+        prices.insert(id, (scaled as u32).max(1));
+    }
+    prices.extend(sold);
+    Ok(prices)
+}
+
+/// Where `RoomMode::StartWithHammer` starts the player: by the hammer, in Goomba Village.
+const HAMMER_START: (&str, u32) = ("kmr_04", 2);
+
+/// How many times to shuffle warps within a `RoomScope` before giving up on connecting them.
+const SCOPED_SHUFFLE_ATTEMPTS: usize = 20;
+
+const ROOM_TABLE_OFFSET: u32 = 0x6B450;
+const ROOM_COUNT: u32 = 421;
+
+/// Read the name and room pointer of the `i`th room in the room table.
+fn read_room_table_entry(rom: &Rom, i: u32) -> Result<(String, u32), RandomizerError> {
+    let entry_offset = ROOM_TABLE_OFFSET + i * 0x20;
+
+    let name_ptr = rom.read_u32(entry_offset)?.wrapping_sub(0x80024C00);
+
+    let room_ptr = rom.read_u32(entry_offset + 8)?;
+
+    let name = read_room_name(rom, name_ptr)?;
+
+    Ok((name, room_ptr))
+}
+
+/// Read the destination room name and entrance of the given warp.
+fn read_warp(rom: &Rom, room_ptr: u32, warp_ptr: u32) -> Result<(String, u32), RandomizerError> {
+    let warp_offset = room_offset(room_ptr, warp_ptr).wrapping_add(0xC);
+    let warp_room_ptr = rom.read_u32(warp_offset)?;
+    let entrance = rom.read_u32(warp_offset.wrapping_add(4))?;
+    let name = read_room_name(rom, room_offset(room_ptr, warp_room_ptr))?;
+
+    Ok((name, entrance))
+}
+
+fn read_room_name(rom: &Rom, offset: u32) -> Result<String, RandomizerError> {
+    const SIZE: usize = 8;
+    let name_buf = rom.read_bytes(offset, SIZE)?;
+
+    let null_location = name_buf.iter().position(|&b| b == 0).unwrap_or(SIZE);
+    String::from_utf8(name_buf[0..null_location].to_vec()).map_err(|_|
+        RandomizerError::WrongRom(format!("invalid room name at {:#x}", offset))
+    )
+}
+
+/// Where rooms are loaded in RAM. Pointers within a room's data are relative to this.
+const ROOM_BASE_PTR: u32 = 0x80240000;
+
+/// Convert a pointer into the room at `room_ptr` into a rom offset. Bad pointers wrap around
+/// into offsets that are out of range, rather than panicking here.
+fn room_offset(room_ptr: u32, ptr: u32) -> u32 {
+    room_ptr.wrapping_add(ptr).wrapping_sub(ROOM_BASE_PTR)
+}
+
+fn get_room<'a>(room_data: &'a RoomData, name: &str) -> Result<&'a Room, RandomizerError> {
+    room_data.get(name).ok_or_else(||
+        RandomizerError::MissingRoomData(format!("no entry for room {:?}", name))
+    )
+}
+
+#[test]
+fn carried_prices_cover_badges_the_shop_never_sold() {
+    let mut rom = Rom::new(vec![0; 0xA3CB80]);
+    // Rowf sells 0xE0 for 10 coins and 0xE1 for 30, and every other slot is 20.
+    for (i, slot) in get_rowf_iter().enumerate() {
+        let (id, price) = match i {
+            0 => (0xE0, 10),
+            1 => (0xE1, 30),
+            _ => (0x100 + i as u32, 20),
+        };
+        rom.write_u32(slot, id).unwrap();
+        rom.write_u32(slot + 4, price).unwrap();
+    }
+    // Merlow sells 0xE2 for 4 star pieces, and everything else for 2.
+    for (i, slot) in get_merlow_iter().enumerate() {
+        let (id, price) = if i == 0 { (0xE2, 4) } else { (0x120 + i as u32, 2) };
+        rom.write_u32(slot, id).unwrap();
+        rom.write_u32(slot + 4, price).unwrap();
+    }
+
+    let rowf = carried_prices(&rom, get_rowf_iter(), get_merlow_iter()).unwrap();
+    assert_eq!(rowf[&0xE1], 30);
+    assert_eq!(rowf[&0xE2], 40);
+    assert_eq!(rowf[&0xE3], 20);
+
+    let merlow = carried_prices(&rom, get_merlow_iter(), get_rowf_iter()).unwrap();
+    assert_eq!(merlow[&0xE0], 1);
+    assert_eq!(merlow[&0xE1], 3);
+    assert_eq!(merlow[&0xE2], 4);
+    assert_eq!(merlow[&0xE3], 2);
+}
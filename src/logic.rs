@@ -0,0 +1,160 @@
+// What the player needs to pick up each item, and placing key items so that they can all be
+// picked up.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::items::{get_item, ItemCategory};
+use crate::xs::{xs_choice, xs_shuffle, Xs};
+use crate::RandomizerError;
+
+// What each item slot needs is in roomdata.json, as `item_needs`. Partners are never needed
+// there because the start patch gives the player every partner, so their abilities are always
+// there. Being stricter than the game only limits where key items can go, so when in doubt,
+// a slot should need more.
+
+/// A map item, what was there in the unmodified rom, and the ids of the items needed to pick it
+/// up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Location {
+    pub room: &'static str,
+    pub item_ptr: u32,
+    pub original: u32,
+    pub needs: Vec<u32>,
+}
+
+/// What is needed to pick up the item at `location`. Items which are not in `pool` are left
+/// where they are, so we assume they can be had just as in the unmodified game.
+fn needs(location: &Location, pool: &HashSet<u32>) -> Vec<u32> {
+    location.needs.iter().copied().filter(|id| pool.contains(id)).collect()
+}
+
+fn is_key_item(id: u32) -> bool {
+    get_item(id).is_some_and(|item| item.category == ItemCategory::KeyItem)
+}
+
+/// Decide what goes in each of `locations`. The key items among the original items are placed
+/// with assumed fill: each one goes somewhere that can be reached while holding every key item
+/// which has not been placed yet, so they can all be collected in the reverse of the order they
+/// were placed. Every other location gets a random item from `filler`.
+///
+/// If `reachable_rooms` is `None`, every room is assumed to be reachable.
+pub(crate) fn place_items(
+    xs: &mut Xs,
+    locations: &[Location],
+    reachable_rooms: Option<&HashSet<&'static str>>,
+    filler: &[u32],
+) -> Result<HashMap<(&'static str, u32), u32>, RandomizerError> {
+    let mut pool: Vec<u32> = locations.iter()
+        .map(|location| location.original)
+        .filter(|&id| is_key_item(id))
+        .collect();
+    let pool_set: HashSet<u32> = pool.iter().copied().collect();
+    let location_needs: Vec<Vec<u32>> = locations.iter()
+        .map(|location| needs(location, &pool_set))
+        .collect();
+
+    let can_reach = |i: usize, owned: &HashSet<u32>| {
+        reachable_rooms.is_none_or(|rooms| rooms.contains(locations[i].room))
+            && location_needs[i].iter().all(|id| owned.contains(id))
+    };
+
+    xs_shuffle(xs, &mut pool);
+
+    let mut placed: Vec<Option<u32>> = vec![None; locations.len()];
+    while let Some(id) = pool.pop() {
+        let mut owned: HashSet<u32> = pool.iter().copied().collect();
+        loop {
+            let before = owned.len();
+            for (i, item) in placed.iter().enumerate() {
+                if let Some(item) = item {
+                    if can_reach(i, &owned) {
+                        owned.insert(*item);
+                    }
+                }
+            }
+            if owned.len() == before {
+                break;
+            }
+        }
+
+        let open: Vec<u32> = (0..locations.len())
+            .filter(|&i| placed[i].is_none() && can_reach(i, &owned))
+            .map(|i| i as u32)
+            .collect();
+        if open.is_empty() {
+            return Err(RandomizerError::Unreachable(format!(
+                "could not find a reachable place for {}",
+                crate::get_item_name(id)
+            )));
+        }
+        placed[xs_choice(xs, &open) as usize] = Some(id);
+    }
+
+    let mut items = HashMap::with_capacity(locations.len());
+    for (location, item) in locations.iter().zip(placed) {
+        let id = match item {
+            Some(id) => id,
+            None => xs_choice(xs, filler),
+        };
+        items.insert((location.room, location.item_ptr), id);
+    }
+
+    Ok(items)
+}
+
+#[test]
+fn placed_key_items_can_all_be_collected() {
+    const HAMMER: u32 = 0x004;
+    const FORTRESS_KEY: u32 = 0x010;
+    const PULSE_STONE: u32 = 0x012;
+    const TOY_TRAIN: u32 = 0x021;
+    const JADE_RAVEN: u32 = 0x02A;
+
+    let location = |room, item_ptr, original, needs: &[u32]| {
+        Location { room, item_ptr, original, needs: needs.to_vec() }
+    };
+    let locations = [
+        location("kmr_00", 1, 0x080, &[]),
+        location("kmr_01", 1, PULSE_STONE, &[]),
+        location("kmr_02", 1, HAMMER, &[]),
+        location("kmr_03", 1, 0x083, &[]),
+        location("kmr_04", 1, 0x084, &[]),
+        location("isk_00", 1, TOY_TRAIN, &[HAMMER, PULSE_STONE]),
+        location("isk_00", 2, 0x081, &[HAMMER, PULSE_STONE]),
+        location("omo_00", 1, FORTRESS_KEY, &[HAMMER, TOY_TRAIN]),
+        location("trd_00", 1, 0x082, &[HAMMER, FORTRESS_KEY]),
+        location("trd_00", 2, JADE_RAVEN, &[HAMMER, FORTRESS_KEY]),
+        location("kzn_00", 1, 0x0E0, &[HAMMER, JADE_RAVEN]),
+    ];
+    let originals: Vec<u32> = locations.iter().map(|l| l.original).collect();
+    let pool: HashSet<u32> = originals.iter().copied().filter(|&id| is_key_item(id)).collect();
+
+    for seed in 1..50 {
+        let xs = &mut crate::xs::xs_from_seed(seed, crate::RngVersion::Uniform);
+        let items = place_items(xs, &locations, None, &[0x080]).unwrap();
+
+        let mut owned = HashSet::new();
+        loop {
+            let before = owned.len();
+            for l in locations.iter() {
+                if needs(l, &pool).iter().all(|id| owned.contains(id)) {
+                    owned.insert(items[&(l.room, l.item_ptr)]);
+                }
+            }
+            if owned.len() == before {
+                break;
+            }
+        }
+        assert!(pool.is_subset(&owned), "seed {}: {:?}", seed, items);
+    }
+}
+
+#[test]
+fn room_data_lists_needs_for_every_item() {
+    for (name, room) in crate::load_room_data().unwrap() {
+        assert!(
+            room.item_needs.is_empty() || room.item_needs.len() == room.items.len(),
+            "{} has {} items but needs for {}", name, room.items.len(), room.item_needs.len()
+        );
+    }
+}
@@ -1,197 +1,81 @@
-type Xs = [core::num::Wrapping<u32>; 4];
-
-fn xorshift(xs: &mut Xs) -> u32 {
-    let mut t = xs[3];
-
-    xs[3] = xs[2];
-    xs[2] = xs[1];
-    xs[1] = xs[0];
-
-    t ^= t << 11;
-    t ^= t >> 8;
-    xs[0] = t ^ xs[0] ^ (xs[0] >> 19);
-
-    xs[0].0
-}
-
-fn xs_u32(xs: &mut Xs, min: u32, one_past_max: u32) -> u32 {
-    (xorshift(xs) % (one_past_max - min)) + min
-}
-
-fn xs_choice_str<'a>(xs: &mut Xs, slice: &[&'a str]) -> &'a str {
-    let len = slice.len();
-    assert!(len < u32::max_value() as _);
-    slice[xs_u32(xs,0, len as u32) as usize]
-}
-
-fn xs_choice(xs: &mut Xs, slice: &[u32]) -> u32 {
-    let len = slice.len();
-    assert!(len < u32::max_value() as _);
-    slice[xs_u32(xs, 0, len as u32) as usize]
-}
-
-fn xs_shuffle(xs: &mut Xs, v: &mut Vec<u32>) {
-    let len = v.len();
-    if len < 2 {
-        return;
-    }
-    for i in 0..(len - 2) {
-        // Oh gee this won't shuffle the whole vec if there are ever 2^32 elements. Meh.
-        let j = xs_u32(xs,i as u32, len as u32) as usize;
-        v.swap(i, j);
-    }
-}
-
-#[test]
-fn xs_test() {
-    let xs: &mut Xs = &mut [Wrapping(42),Wrapping(42),Wrapping(42),Wrapping(42)];
-    for _ in 0..1000 {
-        xs_u32(xs, 1, 0x16C);
+use std::io::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use paper_mario_randomizer::{
+    byte_order,
+    byte_order::ByteOrder,
+    permalink,
+    preset,
+    randomize_with_spoiler,
+    room_eligibility,
+    seed_hash,
+    seed_from_str,
+    verify,
+    RandomizerError,
+    BadgeSections,
+    ItemMode,
+    PriceMode,
+    PriceRange,
+    RngVersion,
+    RoomEligibility,
+    RoomFilter,
+    RoomMode,
+    RoomScope,
+    Settings,
+    StartMode,
+    StartRoom,
+    WarpRules,
+    PRESET_NAMES,
+};
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(error.exit_code())
     }
-
-    for _ in 0..10 {
-        xs_u32(xs, 1, 0x16C);
-        dbg!(&xs);
-    }
-    panic!()
-}
-
-use std::io::{SeekFrom, prelude::*};
-use std::{fs, fs::OpenOptions};
-use std::collections::{HashMap, HashSet};
-
-use serde::{Deserialize, Serialize};
-use std::path::Path;
-use std::ffi::CString;
-use std::num::Wrapping;
-use std::ops::RangeInclusive;
-use std::iter::StepBy;
-use std::convert::TryFrom;
-
-#[derive(Serialize, Deserialize)]
-struct Room {
-    entrances: Vec<u32>,
-    items: Vec<u32>,
-    warp_ptrs: Vec<u32>,
-}
-
-macro_rules! d {
-    () => {
-        Default::default();
-    };
-    (for $type: ty : $value: expr) => {
-        impl Default for $type {
-            fn default() -> Self {
-                $value
-            }
-        }
-    };
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input_path = "./Paper Mario (USA).z64";
-    let output_path = "./Paper Mario (USA) Shuffled.z64";
-
-    #[derive(Copy, Clone, PartialEq, Eq)]
-    enum RoomMode {
-        None,
-        StartWithHammer,
-        TotalRandom
-    }
-    d!(for RoomMode : RoomMode::StartWithHammer);
-
-    enum StartMode {
-        Standard,
-        Quick
-    }
-    d!(for StartMode : StartMode::Standard);
-
-    #[repr(u8)]
-    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-    enum BadgeSections {
-        Map = 0b001,
-        Rowf = 0b010,
-        RowfMap = 0b011,
-        Merlow = 0b100,
-        MerlowMap = 0b101,
-        MerlowRowf = 0b110,
-        MerlowRowfMap = 0b111,
-    }
-
-    impl BadgeSections {
-        // if `other` contains two or more set bits, this is true only if all of those bits are set
-        // in `self`.
-        fn contains(self, other: Self) -> bool {
-            self as u8 & other as u8 == other as u8
-        }
-    }
-
-    impl TryFrom<u8> for BadgeSections {
-        type Error = &'static str;
-
-        fn try_from(value: u8) -> Result<Self, Self::Error> {
-            use BadgeSections::*;
-            match value {
-                 0b001 => Ok(Map),
-                 0b010 => Ok(Rowf),
-                 0b011 => Ok(RowfMap),
-                 0b100 => Ok(Merlow),
-                 0b101 => Ok(MerlowMap),
-                 0b110 => Ok(MerlowRowf),
-                 0b111 => Ok(MerlowRowfMap),
-                _ => Err("Invalid BadgeSections")
-            }
-        }
-    }
-
-    impl std::ops::BitOr for BadgeSections {
-        type Output = BadgeSections;
-
-        fn bitor(self, rhs: Self) -> Self::Output {
-            if let Ok(s) = BadgeSections::try_from(self as u8 | rhs as u8) {
-                s
-            } else {
-                unreachable!()
-            }
-        }
-    }
-
-    impl std::ops::BitOrAssign for BadgeSections {
-        fn bitor_assign(&mut self, rhs: Self) {
-            *self = *self | rhs
-        }
-    }
-
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    enum ItemMode {
-        None,
-        TotalRandom,
-        ShuffleBadgesGlobally,
-        ShuffleBadgesLocally(BadgeSections),
-        DealUsedInto(BadgeSections),
-        DealAllInto(BadgeSections),
-    }
-    d!(for ItemMode : ItemMode::None);
+fn run() -> Result<(), RandomizerError> {
+    const DEFAULT_INPUT_PATH: &str = "./Paper Mario (USA).z64";
+    const DEFAULT_OUTPUT_PATH: &str = "./Paper Mario (USA) Shuffled.z64";
 
     let mut args = std::env::args();
     //exe name
     args.next();
 
-    let mut start = d!();
-    let mut item_mode = d!();
-    let mut room_mode = d!();
-
-    const TOTALLY_RANDOMIZE_MAP_ITEMS: &'static str = "--totally-randomize-map-items";
-    const SHUFFLE_BADGES: &'static str = "--shuffle-badges-globally";
-    const SHUFFLE_MAP_BADGES: &'static str = "--shuffle-map-badges-locally";
-    const SHUFFLE_ROWF_BADGES: &'static str = "--shuffle-rowf-badges-locally";
-    const SHUFFLE_MERLOW_BADGES: &'static str = "--shuffle-merlow-badges-locally";
-    const DEAL_USED_INTO_MAP: &'static str = "--deal-badges-into-map";
-    const DEAL_USED_INTO_ROWF: &'static str = "--deal-badges-into-rowf";
-    const DEAL_USED_INTO_MERLOW: &'static str = "--deal-badges-into-merlow";
-    const DEAL_ALL_INTO_MAP: &'static str = "--deal-from-all-badges-into-map";
-    const DEAL_ALL_INTO_ROWF: &'static str = "--deal-from-all-badges-into-rowf";
-    const DEAL_ALL_INTO_MERLOW: &'static str = "--deal-from-all-badges-into-merlow";
+    let mut start = StartMode::default();
+    let mut start_room = StartRoom::default();
+    let mut item_mode = ItemMode::default();
+    let mut room_mode = RoomMode::default();
+    let mut room_scope = RoomScope::default();
+    let mut warp_rules = WarpRules::default();
+    let mut shop_prices = PriceMode::default();
+    let mut rng_version = RngVersion::default();
+    let mut keep_input_byte_order = false;
+    let mut skip_rom_check = false;
+    let mut write_spoiler_log = true;
+    let mut dump_graph = false;
+    let mut input_path = DEFAULT_INPUT_PATH.to_owned();
+    let mut output_path = None;
+    let mut output_dir = None;
+    let mut base_settings: Option<Settings> = None;
+    let mut save_settings_path = None;
+    let mut list_rooms = false;
+    let mut excluded_rooms: Vec<String> = Vec::new();
+    let mut included_rooms: Vec<String> = Vec::new();
+
+    const TOTALLY_RANDOMIZE_MAP_ITEMS: &str = "--totally-randomize-map-items";
+    const SHUFFLE_BADGES: &str = "--shuffle-badges-globally";
+    const SHUFFLE_MAP_BADGES: &str = "--shuffle-map-badges-locally";
+    const SHUFFLE_ROWF_BADGES: &str = "--shuffle-rowf-badges-locally";
+    const SHUFFLE_MERLOW_BADGES: &str = "--shuffle-merlow-badges-locally";
+    const DEAL_USED_INTO_MAP: &str = "--deal-badges-into-map";
+    const DEAL_USED_INTO_ROWF: &str = "--deal-badges-into-rowf";
+    const DEAL_USED_INTO_MERLOW: &str = "--deal-badges-into-merlow";
+    const DEAL_ALL_INTO_MAP: &str = "--deal-from-all-badges-into-map";
+    const DEAL_ALL_INTO_ROWF: &str = "--deal-from-all-badges-into-rowf";
+    const DEAL_ALL_INTO_MERLOW: &str = "--deal-from-all-badges-into-merlow";
 
     macro_rules! set_item_mode {
         ($mode: expr) => {{
@@ -214,7 +98,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 |(ItemMode::ShuffleBadgesLocally(_), _)
                 |(ItemMode::DealUsedInto(_), _)
                 |(ItemMode::DealAllInto(_), _) => {
-                    eprintln!(
+                    return Err(RandomizerError::BadArguments(format!(
                         "Of the flags {:?} only the groups {:?}, {:?}, and {:?} can be mixed together, and only within their own group, not together.",
                         [
                             TOTALLY_RANDOMIZE_MAP_ITEMS,
@@ -241,38 +125,132 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             DEAL_ALL_INTO_ROWF,
                             DEAL_ALL_INTO_MERLOW,
                         ],
-                    );
-                    std::process::exit(2)
+                    )));
                 },
             }
 
         }};
     }
 
-    const TOTALLY_RANDOMIZE_ROOMS: &'static str = "--totally-randomize-rooms";
-    const NO_ROOM_RANDOMIZATION: &'static str = "--no-room-randomization";
+    const TOTALLY_RANDOMIZE_ROOMS: &str = "--totally-randomize-rooms";
+    const NO_ROOM_RANDOMIZATION: &str = "--no-room-randomization";
+    const COUPLED_ROOMS: &str = "--coupled-room-randomization";
 
     macro_rules! set_room_mode {
         ($mode: expr) => {{
-            if room_mode != d!() {
-                eprintln!(
+            if room_mode != RoomMode::default() {
+                return Err(RandomizerError::BadArguments(format!(
                     "Only one of {:?} may be used.",
-                     [TOTALLY_RANDOMIZE_ROOMS, NO_ROOM_RANDOMIZATION]
-                 );
-                std::process::exit(3)
+                     [TOTALLY_RANDOMIZE_ROOMS, NO_ROOM_RANDOMIZATION, COUPLED_ROOMS]
+                 )));
             }
             room_mode = $mode;
         }};
     }
 
-    const VERSION: &'static str = "--version";
-    const HELP: &'static str = "--help";
-    const QUICK_START: &'static str = "--quick-start";
+    const ROOMS_WITHIN_AREAS: &str = "--randomize-rooms-within-areas";
+    const ROOMS_BETWEEN_AREAS: &str = "--randomize-rooms-between-areas";
+    const DUNGEON_ROOMS: &str = "--randomize-dungeon-rooms-only";
+
+    macro_rules! set_room_scope {
+        ($scope: expr) => {{
+            if room_scope != RoomScope::default() {
+                return Err(RandomizerError::BadArguments(format!(
+                    "Only one of {:?} may be used.",
+                     [ROOMS_WITHIN_AREAS, ROOMS_BETWEEN_AREAS, DUNGEON_ROOMS]
+                 )));
+            }
+            room_scope = $scope;
+        }};
+    }
 
-    const SEED: &'static str = "--seed";
+    const IGNORE_WARP_RULES: &str = "--ignore-warp-rules";
+
+    const CARRY_PRICES: &str = "--carry-shop-prices";
+    const RANDOMIZE_PRICES: &str = "--randomize-shop-prices";
+    const COIN_PRICES: &str = "--coin-prices";
+    const STAR_PIECE_PRICES: &str = "--star-piece-prices";
+
+    const DEFAULT_RANDOM_PRICES: PriceMode = PriceMode::Random {
+        coins: PriceRange::DEFAULT_COINS,
+        star_pieces: PriceRange::DEFAULT_STAR_PIECES,
+    };
+    let price_flag_conflict = || RandomizerError::BadArguments(format!(
+        "{} cannot be used with any of {:?}.",
+        CARRY_PRICES,
+        [RANDOMIZE_PRICES, COIN_PRICES, STAR_PIECE_PRICES]
+    ));
+
+    macro_rules! set_price_range {
+        ($flag: expr, $range: ident) => {{
+            let flag = $flag;
+            let bad_range = || RandomizerError::BadArguments(
+                format!("{0} needs a range of prices. For example: {0} 10-50", flag)
+            );
+            let arg = args.next().ok_or_else(bad_range)?;
+            let mut parts = arg.splitn(2, '-').map(|part| part.trim().parse::<u16>());
+            let range = match (parts.next(), parts.next()) {
+                (Some(Ok(min)), Some(Ok(max))) => PriceRange { min, max },
+                _ => return Err(bad_range()),
+            };
+            if shop_prices == PriceMode::Keep {
+                shop_prices = DEFAULT_RANDOM_PRICES;
+            }
+            match &mut shop_prices {
+                PriceMode::Random { $range, .. } => *$range = range,
+                PriceMode::Keep | PriceMode::Carry => return Err(price_flag_conflict()),
+            }
+        }};
+    }
+
+    const VERSION: &str = "--version";
+    const HELP: &str = "--help";
+    const QUICK_START: &str = "--quick-start";
+    const KEEP_INPUT_BYTE_ORDER: &str = "--keep-input-byte-order";
+    const SKIP_ROM_CHECK: &str = "--skip-rom-check";
+    const NO_SPOILER_LOG: &str = "--no-spoiler-log";
+    const DUMP_GRAPH: &str = "--dump-graph";
+    const LEGACY_RNG: &str = "--legacy-rng";
+    const LIST_ROOMS: &str = "--list-rooms";
+
+    const SEED: &str = "--seed";
+    const INPUT: &str = "--input";
+    const OUTPUT: &str = "--output";
+    const OUTPUT_DIR: &str = "--output-dir";
+    const SETTINGS: &str = "--settings";
+    const PRESET: &str = "--preset";
+    const SAVE_SETTINGS: &str = "--save-settings";
+    const PERMALINK: &str = "--permalink";
+    const EXCLUDE_ROOMS: &str = "--exclude-rooms";
+    const INCLUDE_ROOMS: &str = "--include-rooms";
+    const START_ROOM: &str = "--start-room";
+
+    macro_rules! room_patterns_arg {
+        ($flag: expr) => {{
+            let flag = $flag;
+            args.next()
+                .ok_or_else(|| RandomizerError::BadArguments(
+                    format!("{0} needs a comma separated list of rooms. For example: {0} tst_*,mac_05", flag)
+                ))?
+                .split(',')
+                .map(|pattern| pattern.trim().to_owned())
+                .collect::<Vec<_>>()
+        }};
+    }
+
+    macro_rules! path_arg {
+        ($flag: expr) => {{
+            let flag = $flag;
+            args.next()
+                .ok_or_else(|| RandomizerError::BadArguments(
+                    format!("{0} needs an argument. For example: {0} ./roms", flag)
+                ))?
+        }};
+    }
 
     // zero is not a legal xor_shift seed anyway, so no need to use an Option here.
     let mut seed: u128 = 0;
+    let mut seed_text = None;
 
     while let Some(s) = args.next() {
         let s: &str = &s;
@@ -282,6 +260,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     VERSION,
                     HELP,
                     QUICK_START,
+                    KEEP_INPUT_BYTE_ORDER,
+                    SKIP_ROM_CHECK,
+                    NO_SPOILER_LOG,
+                    DUMP_GRAPH,
+                    LEGACY_RNG,
+                    LIST_ROOMS,
                     TOTALLY_RANDOMIZE_MAP_ITEMS,
                     SHUFFLE_BADGES,
                     SHUFFLE_MAP_BADGES,
@@ -295,24 +279,95 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     DEAL_ALL_INTO_MERLOW,
                     TOTALLY_RANDOMIZE_ROOMS,
                     NO_ROOM_RANDOMIZATION,
-                    SEED
+                    COUPLED_ROOMS,
+                    ROOMS_WITHIN_AREAS,
+                    ROOMS_BETWEEN_AREAS,
+                    DUNGEON_ROOMS,
+                    IGNORE_WARP_RULES,
+                    CARRY_PRICES,
+                    RANDOMIZE_PRICES,
+                    COIN_PRICES,
+                    STAR_PIECE_PRICES,
+                    SEED,
+                    INPUT,
+                    OUTPUT,
+                    OUTPUT_DIR,
+                    SETTINGS,
+                    PRESET,
+                    SAVE_SETTINGS,
+                    PERMALINK,
+                    EXCLUDE_ROOMS,
+                    INCLUDE_ROOMS,
+                    START_ROOM,
                 ];
-                println!("reads {}, writes to {}", input_path, output_path);
                 println!("accepted args: ");
                 for arg in accepted_args.iter() {
                     print!("    {}", arg);
-                    if *arg == SEED {
-                        print!(" <positive number>");
+                    match *arg {
+                        SEED => print!(" <positive number, or any text>"),
+                        LEGACY_RNG => print!(" (uses the biased random number helpers from before they were fixed; this does not make the roms of older versions again)"),
+                        ROOMS_WITHIN_AREAS => print!(" (only warps inside an area, like kmr_* or kpa_*, lead somewhere else in that area)"),
+                        ROOMS_BETWEEN_AREAS => print!(" (only warps from one area to another are changed)"),
+                        DUNGEON_ROOMS => print!(" (like {}, but only in dungeons)", ROOMS_WITHIN_AREAS),
+                        IGNORE_WARP_RULES => print!(" (lets dead ends lead to dead ends, and one-way warps lead to rooms with no way out)"),
+                        CARRY_PRICES => print!(" (badges in Rowf's and Merlow's shops cost what they did in the unmodified game)"),
+                        RANDOMIZE_PRICES => print!(" (badges in Rowf's and Merlow's shops get random prices)"),
+                        COIN_PRICES => print!(
+                            " <min>-<max> (Rowf's random prices, default {}-{})",
+                            PriceRange::DEFAULT_COINS.min,
+                            PriceRange::DEFAULT_COINS.max
+                        ),
+                        STAR_PIECE_PRICES => print!(
+                            " <min>-<max> (Merlow's random prices, default {}-{})",
+                            PriceRange::DEFAULT_STAR_PIECES.min,
+                            PriceRange::DEFAULT_STAR_PIECES.max
+                        ),
+                        INPUT => print!(" <path> (default {:?})", DEFAULT_INPUT_PATH),
+                        OUTPUT => print!(" <path> (default {:?})", DEFAULT_OUTPUT_PATH),
+                        OUTPUT_DIR => print!(
+                            " <path> (writes a file named after the seed, seed hash and settings into this directory instead of {})",
+                            OUTPUT
+                        ),
+                        SETTINGS => print!(" <path> (a JSON file, like the ones {} writes)", SAVE_SETTINGS),
+                        PRESET => print!(" <name> (one of {:?})", PRESET_NAMES),
+                        SAVE_SETTINGS => print!(" <path> (writes the settings used, including the seed)"),
+                        PERMALINK => print!(" <permalink> (as printed when randomizing, holds the seed and every mode)"),
+                        DUMP_GRAPH => print!(" (writes the warps between rooms, before and after randomizing, as Graphviz DOT and JSON files next to the rom)"),
+                        LIST_ROOMS => print!(" (lists which rooms room randomization can use, and why, then exits)"),
+                        EXCLUDE_ROOMS => print!(
+                            " <rooms> (comma separated room names or prefixes like tst_*, which room randomization will not use)"
+                        ),
+                        INCLUDE_ROOMS => print!(
+                            " <rooms> (like {}, but for rooms to use even though they are excluded)",
+                            EXCLUDE_ROOMS
+                        ),
+                        START_ROOM => print!(
+                            " <room>[:<entrance>] or random (where leaving kmr_00 leads, right away with {} or after the prologue otherwise)",
+                            QUICK_START
+                        ),
+                        _ => {}
                     }
                     println!()
                 }
-                std::process::exit(0)
+                println!("exit codes: ");
+                for (code, description) in RandomizerError::exit_code_descriptions().iter() {
+                    println!("    {}: {}", code, description);
+                }
+                return Ok(())
             },
             VERSION => {
                 println!("version {}", env!("CARGO_PKG_VERSION"));
-                std::process::exit(0)
+                return Ok(())
             },
             QUICK_START => start = StartMode::Quick,
+            KEEP_INPUT_BYTE_ORDER => keep_input_byte_order = true,
+            SKIP_ROM_CHECK => skip_rom_check = true,
+            NO_SPOILER_LOG => write_spoiler_log = false,
+            DUMP_GRAPH => dump_graph = true,
+            LEGACY_RNG => rng_version = RngVersion::Legacy,
+            LIST_ROOMS => list_rooms = true,
+            EXCLUDE_ROOMS => excluded_rooms.extend(room_patterns_arg!(EXCLUDE_ROOMS)),
+            INCLUDE_ROOMS => included_rooms.extend(room_patterns_arg!(INCLUDE_ROOMS)),
             TOTALLY_RANDOMIZE_MAP_ITEMS => set_item_mode!(ItemMode::TotalRandom),
             SHUFFLE_BADGES => set_item_mode!(ItemMode::ShuffleBadgesGlobally),
             SHUFFLE_MAP_BADGES => set_item_mode!(ItemMode::ShuffleBadgesLocally(BadgeSections::Map)),
@@ -326,124 +381,150 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             DEAL_ALL_INTO_MERLOW => set_item_mode!(ItemMode::DealAllInto(BadgeSections::Merlow)),
             NO_ROOM_RANDOMIZATION => set_room_mode!(RoomMode::None),
             TOTALLY_RANDOMIZE_ROOMS => set_room_mode!(RoomMode::TotalRandom),
+            COUPLED_ROOMS => set_room_mode!(RoomMode::Coupled),
+            ROOMS_WITHIN_AREAS => set_room_scope!(RoomScope::WithinAreas),
+            ROOMS_BETWEEN_AREAS => set_room_scope!(RoomScope::BetweenAreas),
+            DUNGEON_ROOMS => set_room_scope!(RoomScope::Dungeons),
+            IGNORE_WARP_RULES => warp_rules = WarpRules::Ignore,
+            CARRY_PRICES => match shop_prices {
+                PriceMode::Keep | PriceMode::Carry => shop_prices = PriceMode::Carry,
+                PriceMode::Random { .. } => return Err(price_flag_conflict()),
+            },
+            RANDOMIZE_PRICES => match shop_prices {
+                PriceMode::Keep => shop_prices = DEFAULT_RANDOM_PRICES,
+                PriceMode::Random { .. } => {},
+                PriceMode::Carry => return Err(price_flag_conflict()),
+            },
+            COIN_PRICES => set_price_range!(COIN_PRICES, coins),
+            STAR_PIECE_PRICES => set_price_range!(STAR_PIECE_PRICES, star_pieces),
             SEED => {
-                seed = args.next()
-                    .ok_or_else(||
-                        format!("{0} needs an argument. For example: {0} 42", SEED)
-                    )?
-                    .parse()?;
-                if seed == 0 {
-                    eprintln!("Interpreting 0 seed as if {} was not passed.", SEED);
+                let arg = args.next()
+                    .ok_or_else(|| RandomizerError::BadArguments(
+                        format!("{0} needs an argument. For example: {0} 42 or {0} weekly-race-42", SEED)
+                    ))?;
+                seed = seed_from_str(&arg);
+                if seed.to_string() != arg {
+                    seed_text = Some(arg);
                 }
             },
+            START_ROOM => {
+                if start_room != StartRoom::default() {
+                    return Err(RandomizerError::BadArguments(format!("Only one {} may be used.", START_ROOM)));
+                }
+                let arg = args.next()
+                    .ok_or_else(|| RandomizerError::BadArguments(
+                        format!("{0} needs an argument. For example: {0} kmr_04:2 or {0} random", START_ROOM)
+                    ))?;
+                start_room = match arg.split_once(':') {
+                    None if arg == "random" => StartRoom::Random,
+                    None => StartRoom::Fixed { room: arg, entrance: 0 },
+                    Some((room, entrance)) => StartRoom::Fixed {
+                        room: room.to_owned(),
+                        entrance: entrance.parse().map_err(|_| RandomizerError::BadArguments(
+                            format!("{:?} is not an entrance number, in {} {}", entrance, START_ROOM, arg)
+                        ))?,
+                    },
+                };
+            },
+            INPUT => input_path = path_arg!(INPUT),
+            OUTPUT => output_path = Some(path_arg!(OUTPUT)),
+            OUTPUT_DIR => output_dir = Some(path_arg!(OUTPUT_DIR)),
+            SETTINGS | PRESET | PERMALINK if base_settings.is_some() => {
+                return Err(RandomizerError::BadArguments(
+                    format!("Only one of {:?} may be used, and only once.", [SETTINGS, PRESET, PERMALINK])
+                ));
+            },
+            PERMALINK => {
+                let arg = args.next()
+                    .ok_or_else(|| RandomizerError::BadArguments(
+                        format!("{} needs an argument, as printed when randomizing.", PERMALINK)
+                    ))?;
+                base_settings = Some(permalink::decode(&arg)?);
+            },
+            SETTINGS => {
+                let path = path_arg!(SETTINGS);
+                let json = fs::read_to_string(&path)?;
+                base_settings = Some(serde_json::from_str(&json).map_err(|e| RandomizerError::BadArguments(
+                    format!("Could not read settings from {}: {}", path, e)
+                ))?);
+            },
+            PRESET => {
+                let name = args.next()
+                    .ok_or_else(|| RandomizerError::BadArguments(
+                        format!("{0} needs an argument. For example: {0} {1}", PRESET, PRESET_NAMES[0])
+                    ))?;
+                base_settings = Some(preset(&name).ok_or_else(|| RandomizerError::BadArguments(
+                    format!("Unknown preset {:?}. The presets are {:?}", name, PRESET_NAMES)
+                ))?);
+            },
+            SAVE_SETTINGS => save_settings_path = Some(path_arg!(SAVE_SETTINGS)),
             _ => {
-                eprintln!("unknown arg {:?}", s);
-                std::process::exit(1)
+                return Err(RandomizerError::BadArguments(format!("unknown arg {:?}", s)))
             }
         }
     }
 
-    fs::copy(input_path, output_path)?;
-
-    let mut output = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(output_path)?;
-
-    let room_data: HashMap<&str, Room> = serde_json::from_str(include_str!("roomdata.json"))?;
-
-    let mut names_to_skip = HashSet::with_capacity(12);
-    names_to_skip.insert("end_00");
-    names_to_skip.insert("end_01");
-    names_to_skip.insert("gv_01");
-    names_to_skip.insert("mgm_03");
-    names_to_skip.insert("tst_11");
-    names_to_skip.insert("tst_12");
-    names_to_skip.insert("tst_13");
-    names_to_skip.insert("tst_20");
-
-    names_to_skip.insert("hos_04");
-    names_to_skip.insert("hos_05");
-    names_to_skip.insert("hos_10");
-    names_to_skip.insert("mac_05");
-
-    let mut room_names = room_data.keys()
-        .filter(|name| {
-            !names_to_skip.contains(*name)
-        })
-        .map(|x| *x)
-        .collect::<Vec<&str>>();
-    room_names.sort();
-
-    // TODO automatically endian-convert if needed.
-    // make sure this is a paper mario rom with proper endianness
-    {
-        output.seek(SeekFrom::Start(0x20))?;
-        let mut buf = [0u8; 11];
-        output.read_exact(&mut buf)?;
-        assert_eq!(&buf, b"PAPER MARIO", "Endian and/or rom type mismatch!");
+    if room_mode == RoomMode::None && room_scope != RoomScope::default() {
+        return Err(RandomizerError::BadArguments(format!(
+            "{} cannot be used with {:?}, which only choose which rooms are randomized.",
+            NO_ROOM_RANDOMIZATION, [ROOMS_WITHIN_AREAS, ROOMS_BETWEEN_AREAS, DUNGEON_ROOMS]
+        )));
     }
 
-    #[allow(unused_assignments)]
-    {
-        let mut offset = 0x808A8;
-        macro_rules! seek_write_advance {
-            ($data: expr) => {
-                output.seek(SeekFrom::Start(offset))?;
-                output.write(&($data as u32).to_be_bytes())?;
-
-                offset += 4;
-            };
+    let mut room_filter = RoomFilter::default();
+    if let Some(base) = base_settings {
+        if start != StartMode::default()
+            || start_room != StartRoom::default()
+            || item_mode != ItemMode::default()
+            || room_mode != RoomMode::default()
+            || room_scope != RoomScope::default()
+            || warp_rules != WarpRules::default()
+            || shop_prices != PriceMode::default()
+            || rng_version != RngVersion::default()
+            || !excluded_rooms.is_empty()
+            || !included_rooms.is_empty()
+        {
+            return Err(RandomizerError::BadArguments(format!(
+                "{:?} already choose every mode and which rooms are used, so they cannot be used with mode flags, {} or {}.",
+                [SETTINGS, PRESET, PERMALINK], EXCLUDE_ROOMS, INCLUDE_ROOMS
+            )));
         }
-        // code patch: start with Goombario out
-        seek_write_advance!(0xA0820012);
-        seek_write_advance!(0xA082000A); // enable action command
-        seek_write_advance!(0x2402FFFF);
-        offset = 0x808E4;
-        seek_write_advance!(0xA0800000);
-
-        // have every party member
-        seek_write_advance!(0xA0A20014);
-
-        // enable menus
-        offset = 0x168074;
-        seek_write_advance!(0x2406FF81);
-
-        if let StartMode::Quick = start {
-            // don't start the game from Mario's house
-            offset = 0x168080;
-            seek_write_advance!(0x24020000);
+        start = base.start;
+        start_room = base.start_room;
+        item_mode = base.item_mode;
+        room_mode = base.room_mode;
+        room_scope = base.room_scope;
+        warp_rules = base.warp_rules;
+        shop_prices = base.shop_prices;
+        room_filter = base.room_filter;
+        rng_version = base.rng_version;
+        // presets, and settings files saved without one, leave the seed to --seed
+        if base.seed != 0 {
+            if seed != 0 {
+                return Err(RandomizerError::BadArguments(format!(
+                    "The {} or {} given already holds a seed, so it cannot be used with {}.",
+                    PERMALINK, SETTINGS, SEED
+                )));
+            }
+            seed = base.seed;
         }
     }
 
-    macro_rules! read_u32 {
-        () => {{
-            let mut buf = [0u8; 4];
-            output.read_exact(&mut buf)?;
-            u32::from_be_bytes(buf)
-        }};
-    }
+    room_filter.exclude.extend(excluded_rooms);
+    room_filter.include.extend(included_rooms);
 
-    let room_base_ptr: u32 = 0x80240000;
-
-    // Set the pointed to exit in the pointed to room at the target room and entrance
-    macro_rules! write_out_room_entrance {
-        (
-            $room_ptr: expr,
-            $warp_ptr: expr,
-            $target_room_name: expr,
-            $target_room_entrance: expr
-        ) => {
-            let room_ptr: u32 = $room_ptr;
-            let warp_ptr: u32 = $warp_ptr;
-            let target_room_name: &str = $target_room_name;
-            let target_room_entrance: u32 = $target_room_entrance;
-            output.seek(SeekFrom::Start((room_ptr + warp_ptr - room_base_ptr + 0xC) as _))?;
-            let warp_room_ptr = read_u32!();
-            output.write(&target_room_entrance.to_be_bytes())?;
-            output.seek(SeekFrom::Start((room_ptr + warp_room_ptr - room_base_ptr) as _))?;
-            output.write(CString::new(target_room_name)?.as_bytes_with_nul())?;
-        };
+    if list_rooms {
+        let rooms = room_eligibility(&room_filter)?;
+        for (name, eligibility) in rooms.iter() {
+            match eligibility {
+                RoomEligibility::Eligible => println!("{}", name),
+                RoomEligibility::Excluded(pattern) => println!("{} (excluded by {})", name, pattern),
+                RoomEligibility::Included(pattern) => println!("{} (included by {})", name, pattern),
+            }
+        }
+        let eligible = rooms.iter().filter(|(_, eligibility)| eligibility.is_eligible()).count();
+        println!("{} of {} rooms can be used", eligible, rooms.len());
+        return Ok(());
     }
 
     if seed == 0 {
@@ -451,544 +532,180 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         seed = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_default()
-            .as_nanos()
+            .as_nanos();
+        println!("No seed was given, so one was made from the time. Pass {} {} to use it again.", SEED, seed);
     }
 
-    println!("using {} as random seed", seed);
-
-    let xs: &mut Xs = &mut [
-        Wrapping(((seed >> 0) & 0xFFFF_FFFF) as u32),
-        Wrapping(((seed >> 32) & 0xFFFF_FFFF) as u32),
-        Wrapping(((seed >> 64) & 0xFFFF_FFFF) as u32),
-        Wrapping(((seed >> 96) & 0xFFFF_FFFF) as u32),
-    ];
-
-    #[derive(Debug)]
-    enum ItemState {
-        None,
-        TotalRandom,
-        BadgeDeck(Vec<u32>)
+    // We always patch a big-endian copy, and convert back at the end if asked to.
+    let mut rom = fs::read(&input_path)?;
+    let input_byte_order = byte_order::detect(&rom)
+        .ok_or_else(|| RandomizerError::WrongRom(
+            format!("{} does not look like an N64 rom", input_path)
+        ))?;
+    byte_order::convert(&mut rom, input_byte_order);
+
+    if skip_rom_check {
+        eprintln!("Skipping rom check. Patching a rom other than a clean USA rom will likely corrupt it.");
+    } else if let Err(problem) = verify::check(&rom) {
+        return Err(RandomizerError::WrongRom(
+            format!("{}\nPass {} to patch it anyway.", problem, SKIP_ROM_CHECK)
+        ));
     }
 
-    let mut item_state = match item_mode {
-        ItemMode::TotalRandom => ItemState::TotalRandom,
-        ItemMode::ShuffleBadgesGlobally => {
-            let mut deck = get_map_badges()
-                .into_iter()
-                .chain(get_rowf_shop_badges())
-                .chain(get_merlow_shop_badges())
-                .collect();
-            xs_shuffle(xs, &mut deck);
-            ItemState::BadgeDeck(deck)
-        },
-        ItemMode::DealUsedInto(sections) if sections.contains(BadgeSections::Map) => {
-            let mut deck = get_used_badges();
-            xs_shuffle(xs, &mut deck);
-            ItemState::BadgeDeck(deck)
-        }
-        ItemMode::DealAllInto(sections) if sections.contains(BadgeSections::Map) => {
-            let mut deck = get_badges_set().into_iter().collect();
-            xs_shuffle(xs, &mut deck);
-            ItemState::BadgeDeck(deck)
-        }
-        ItemMode::ShuffleBadgesLocally(sections) if sections.contains(BadgeSections::Map) => {
-            let mut deck = get_map_badges();
-            xs_shuffle(xs,&mut deck);
-            ItemState::BadgeDeck(deck)
-        },
-        ItemMode::None | ItemMode::DealUsedInto(_) | ItemMode::DealAllInto(_) | ItemMode::ShuffleBadgesLocally(_) => ItemState::None,
+    let settings = Settings {
+        seed,
+        start,
+        start_room,
+        item_mode,
+        room_mode,
+        room_scope,
+        warp_rules,
+        shop_prices,
+        room_filter,
+        rng_version,
     };
+    // before anything is made from them, since settings files and permalinks skip the flag checks
+    settings.check()?;
 
-    let room_count = 421;
-    for i in 0..room_count {
-        output.seek(SeekFrom::Start(0x6B450 + i * 0x20))?;
-
-        let name_ptr = read_u32!() - 0x80024C00;
-
-        output.seek(SeekFrom::Current(4))?;
-        let room_ptr = read_u32!();
-
-        output.seek(SeekFrom::Start(name_ptr as _))?;
-        let name_buf = {
-            const SIZE: usize = 8;
-            let mut name_buf = [0u8; SIZE];
-            output.read_exact(&mut name_buf)?;
-
-            let mut null_location = 0;
-            for i in 0..SIZE {
-                if name_buf[i] == 0 {
-                    null_location = i;
-                    break;
-                }
-            }
-            std::ffi::CString::new(&name_buf[0..null_location])?
-        };
-        let name = name_buf.to_str()?;
-
-        match room_mode {
-            RoomMode::None => {},
-            RoomMode::TotalRandom | RoomMode::StartWithHammer => {
-                for &warp_ptr in room_data[name].warp_ptrs.iter() {
-                    let rand_room = xs_choice_str(xs, &room_names);
-                    let rand_entrance = xs_choice(xs, &room_data[rand_room].entrances);
-
-                    write_out_room_entrance!(room_ptr, warp_ptr, rand_room, rand_entrance);
-                }
-            },
-        }
-
-        match &mut item_state {
-            ItemState::None => {}
-            ItemState::BadgeDeck(deck) => {
-                // shuffle room badges
-                let badges_set = get_badges_set();
-                for item_ptr in room_data[name].items.iter() {
-                    output.seek(SeekFrom::Start((room_ptr + item_ptr - room_base_ptr) as _))?;
-                    let read_u32 = read_u32!();
-                    if badges_set.contains(&read_u32) {
-                        output.seek(SeekFrom::Current(-4))?;
-                        if let Some(item) = deck.pop() {
-                            output.write(&item.to_be_bytes())?;
-                        }
-                    }
-                }
-            }
-            ItemState::TotalRandom => {
-                for item_ptr in room_data[name].items.iter() {
-                    output.seek(SeekFrom::Start((room_ptr + item_ptr - room_base_ptr) as _))?;
-                    let rand_item = xs_u32(xs, 1, 0x16C);
-                    let read_u32 = read_u32!();
-                    if 0 < read_u32 && read_u32 < 0x200 {
-                        if get_badges_set().contains(&read_u32) {
-                            println!("{:#010x}",read_u32);
-                        }
-                        output.seek(SeekFrom::Current(-4))?;
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-        }
-    }
-
-    match room_mode {
-        RoomMode::TotalRandom | RoomMode::None => {},
-        RoomMode::StartWithHammer => {
-            // start by the hammer by making "kmr_00" (Mario's fall area) lead there.
-            write_out_room_entrance!(0x8ABF90, 2149846604, "kmr_04", 02);
-            write_out_room_entrance!(0x8ABF90, 2149854336, "kmr_04", 02);
-        },
-    }
-
-    match item_mode {
-        ItemMode::None => {},
-        ItemMode::TotalRandom => {
-            // TODO if we stuff non-badge item ids in the badge shops here does it work?
-
-            let badges: Vec<_> = get_badges_set().into_iter().collect();
-
-            for shop_slot in get_rowf_iter() {
-                output.seek(SeekFrom::Start((shop_slot) as _))?;
-                let rand_item = xs_choice(xs,&badges[..]);
-                output.write(&rand_item.to_be_bytes())?;
-            }
+    let extension = if keep_input_byte_order {
+        input_byte_order.extension()
+    } else {
+        ByteOrder::BigEndian.extension()
+    };
 
-            for shop_slot in get_merlow_iter() {
-                output.seek(SeekFrom::Start((shop_slot) as _))?;
-                let rand_item = xs_choice(xs,&badges[..]);
-                output.write(&rand_item.to_be_bytes())?;
-            }
+    let output_path: PathBuf = match (output_path, output_dir) {
+        (Some(_), Some(_)) => {
+            return Err(RandomizerError::BadArguments(
+                format!("Only one of {:?} may be used.", [OUTPUT, OUTPUT_DIR])
+            ));
         },
-        ItemMode::ShuffleBadgesGlobally => {
-            match item_state {
-                ItemState::BadgeDeck(mut deck) => {
-                    for shop_slot in get_rowf_iter() {
-                        output.seek(SeekFrom::Start((shop_slot) as _))?;
-                        if let Some(rand_item) = deck.pop() {
-                            output.write(&rand_item.to_be_bytes())?;
-                        }
-                    }
-
-                    for shop_slot in get_merlow_iter() {
-                        output.seek(SeekFrom::Start((shop_slot) as _))?;
-                        if let Some(rand_item) = deck.pop() {
-                            output.write(&rand_item.to_be_bytes())?;
-                        }
-                    }
-                }
-                other => {
-                    eprintln!(
-                        "unexpected state: {:?} but {:?}",
-                        item_mode,
-                        other
-                    );
-                }
+        (Some(path), None) => {
+            let path = PathBuf::from(path);
+            let matches = path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case(extension));
+            if !matches {
+                eprintln!("The rom will be written in {} byte order, but {:?} does not end in .{}.", extension, path, extension);
             }
+            path
         },
-        ItemMode::ShuffleBadgesLocally(sections) => {
-            if sections.contains(BadgeSections::Rowf) {
-                let mut deck = get_rowf_shop_badges();
-                xs_shuffle(xs,&mut deck);
-
-                for shop_slot in get_rowf_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-
-            if sections.contains(BadgeSections::Merlow) {
-                let mut deck = get_merlow_shop_badges();
-                xs_shuffle(xs,&mut deck);
-
-                for shop_slot in get_merlow_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-        },
-        ItemMode::DealUsedInto(sections) => {
-            if sections.contains(BadgeSections::Rowf) {
-                let mut deck = get_used_badges();
-                xs_shuffle(xs, &mut deck);
-
-                for shop_slot in get_rowf_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-
-            if sections.contains(BadgeSections::Merlow) {
-                let mut deck = get_used_badges();
-                xs_shuffle(xs, &mut deck);
-
-                for shop_slot in get_merlow_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
+        (None, None) => Path::new(DEFAULT_OUTPUT_PATH).with_extension(extension),
+        (None, Some(dir)) => {
+            let room_tag = match settings.room_mode {
+                RoomMode::None => "rooms-none",
+                RoomMode::StartWithHammer => "rooms-hammer",
+                RoomMode::TotalRandom => "rooms-random",
+                RoomMode::Coupled => "rooms-coupled",
+            };
+            let scope_tag = match settings.room_scope {
+                RoomScope::All => "",
+                RoomScope::WithinAreas => "-within-areas",
+                RoomScope::BetweenAreas => "-between-areas",
+                RoomScope::Dungeons => "-dungeons",
+            };
+            let item_tag = match settings.item_mode {
+                ItemMode::None => "items-none".to_owned(),
+                ItemMode::TotalRandom => "items-random".to_owned(),
+                ItemMode::ShuffleBadgesGlobally => "badges-global".to_owned(),
+                ItemMode::ShuffleBadgesLocally(sections) => format!("badges-local-{:?}", sections),
+                ItemMode::DealUsedInto(sections) => format!("deal-used-{:?}", sections),
+                ItemMode::DealAllInto(sections) => format!("deal-all-{:?}", sections),
+            };
+            let start_tag = match settings.start {
+                StartMode::Standard => "",
+                StartMode::Quick => "_quick",
+            };
+            let start_room_tag = match &settings.start_room {
+                StartRoom::Default => String::new(),
+                StartRoom::Fixed { room, entrance } => format!("_start-{}-{}", room, entrance),
+                StartRoom::Random => "_start-random".to_owned(),
+            };
+            // The tags only name the main modes, so the seed hash, which covers every setting,
+            // keeps roms made with different settings from overwriting each other.
+            fs::create_dir_all(&dir)?;
+            Path::new(&dir).join(format!(
+                "Paper Mario {}_{}_{}{}_{}{}{}.{}",
+                seed,
+                seed_hash(&settings).code,
+                room_tag,
+                scope_tag,
+                item_tag.to_lowercase(),
+                start_tag,
+                start_room_tag,
+                extension
+            ))
         },
-        ItemMode::DealAllInto(sections) => {
-            if sections.contains(BadgeSections::Rowf) {
-                let mut deck = get_badges_set().into_iter().collect();
-                xs_shuffle(xs, &mut deck);
-
-                for shop_slot in get_rowf_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-
-            if sections.contains(BadgeSections::Merlow) {
-                let mut deck = get_badges_set().into_iter().collect();
-                xs_shuffle(xs, &mut deck);
+    };
 
-                for shop_slot in get_merlow_iter() {
-                    output.seek(SeekFrom::Start((shop_slot) as _))?;
-                    if let Some(rand_item) = deck.pop() {
-                        output.write(&rand_item.to_be_bytes())?;
-                    }
-                }
-            }
-        },
+    match seed_text {
+        Some(text) => println!(
+            "using {} (from {:?}) as random seed, seed hash {}",
+            seed,
+            text,
+            seed_hash(&settings)
+        ),
+        None => println!("using {} as random seed, seed hash {}", seed, seed_hash(&settings)),
     }
 
+    if let Some(path) = save_settings_path {
+        let json = serde_json::to_string_pretty(&settings).map_err(std::io::Error::from)?;
+        write_atomically(Path::new(&path), json.as_bytes())?;
+        println!("wrote settings to {}", path);
+    }
 
-    output.sync_data()?;
-    drop(output);
+    println!("permalink: {}", permalink::encode(&settings));
 
-    let rn64crc_path = Path::new("rn64crc\\rn64crc.exe");
-    assert!(rn64crc_path.exists(), "rn64crc not found! File was written but the crc was not set.");
+    let (mut rom, spoiler) = randomize_with_spoiler(&rom, &settings)?;
+    if keep_input_byte_order {
+        byte_order::convert(&mut rom, input_byte_order);
+    }
 
-    std::process::Command::new(rn64crc_path)
-        .args(&[output_path, "-u"])
-        .output()
-        .expect("failed to execute rn64crc");
+    write_atomically(&output_path, &rom)?;
 
-    Ok(())
-}
+    if write_spoiler_log {
+        let text_path = output_path.with_extension("spoiler.txt");
+        write_atomically(&text_path, spoiler.to_string().as_bytes())?;
 
-/// iterate over the locations of the 12 byte chunks of rowf's shop data
-fn get_rowf_iter() -> StepBy<RangeInclusive<u32>> {
-    (0x808808..=0x8088C7).step_by(12)
-}
+        let json_path = output_path.with_extension("spoiler.json");
+        let json = serde_json::to_string_pretty(&spoiler).map_err(std::io::Error::from)?;
+        write_atomically(&json_path, json.as_bytes())?;
 
-/// iterate over the locations of the 12 byte chunks of merlow's shop data
-fn get_merlow_iter() -> StepBy<RangeInclusive<u32>> {
-    (0xA3CACC..=0xA3CB7F).step_by(12)
-}
+        println!("wrote spoiler log to {} and {}", text_path.display(), json_path.display());
+    }
 
+    if dump_graph {
+        for (name, graph) in [("vanilla-graph", spoiler.vanilla_graph()), ("randomized-graph", spoiler.randomized_graph())] {
+            let dot_path = output_path.with_extension(format!("{}.dot", name));
+            write_atomically(&dot_path, graph.to_dot().as_bytes())?;
 
-fn get_badges_set() -> HashSet<u32> {
-    // source: http://shrines.rpgclassics.com/n64/papermario/hacking.shtml
-    // endian swapped from the above.
-    vec![
-        0x00E0, // Spin Smash
-        0x00E1, // Multibounce
-        0x00E2, // Power Plus
-        0x00E3, // Dodge Master
-        0x00E4, // Power Bounce
-        0x00E5, // Spike Shield
-        0x00E6, // First Attack
-        0x00E7, // HP Plus
-        0x00E8, // Quake Hammer
-        0x00E9, // Double Dip
-        0x00EB, // Sleep Stomp
-        0x00EC, // Fire Shield
-        0x00ED, // Quick Change
-        0x00EE, // D-Down Pound
-        0x00EF, // Dizzy Stomp
-        0x00F1, // Pretty Lucky
-        0x00F2, // Feeling Fine
-        0x00F3, // Attack FX A
-        0x00F4, // All or Nothing
-        0x00F5, // HP Drain
-        0x00F7, // Slow Go
-        0x00F8, // FP Plus
-        0x00F9, // Mega Rush
-        0x00FA, // Ice Power
-        0x00FB, // Defend Plus
-        0x00FC, // Pay Off
-        0x00FD, // Money Money
-        0x00FE, // Chill Out
-        0x00FF, // Happy Heart
-        0x0100, // Zap Tap
-        0x0102, // Right On!
-        0x0103, // Runaway Pay
-        0x0104, // Refund
-        0x0105, // Flower Saver
-        0x0106, // Triple Dip
-        0x0107, // Hammer Throw
-        0x0108, // Mega Quake
-        0x0109, // Smash Charge
-        0x010A, // Jump Charge
-        0x010B, // S. Smash Chg.
-        0x010C, // S. Jump Chg.
-        0x010D, // Power Rush
-        0x0111, // Last Stand
-        0x0112, // Close Call
-        0x0113, // P-Up, D-Down
-        0x0114, // Lucky Day
-        0x0116, // P-Down, D-Up
-        0x0117, // Power Quake
-        0x011A, // Heart Finder
-        0x011B, // Flower Finder
-        0x011C, // Spin Attack
-        0x011D, // Dizzy Attack
-        0x011E, // I Spy
-        0x011F, // Speedy Spin
-        0x0120, // Bump Attack
-        0x0121, // Power Jump
-        0x0123, // Mega Jump
-        0x0124, // Power Smash
-        0x0126, // Mega Smash
-        0x0127, // Power Smash
-        0x0128, // Power Smash
-        0x0129, // Deep Focus
-        0x012B, // Shrink Smash
-        0x012E, // D-Down Jump
-        0x012F, // Shrink Stomp
-        0x0130, // Damage Dodge
-        0x0132, // Deep Focus
-        0x0133, // Deep Focus
-        0x0134, // HP Plus
-        0x0135, // FP Plus
-        0x0136, // Happy Heart
-        0x0137, // Happy Heart
-        0x0138, // Flower Saver
-        0x0139, // Flower Saver
-        0x013A, // Damage Dodge
-        0x013B, // Damage Dodge
-        0x013C, // Power Plus
-        0x013D, // Power Plus
-        0x013E, // Defend Plus
-        0x013F, // Defend Plus
-        0x0140, // Happy Flower
-        0x0141, // Happy Flower
-        0x0142, // Happy Flower
-        0x0143, // Group Focus
-        0x0144, // Peekaboo
-        0x0145, // Attack FX D
-        0x0146, // Attack FX B
-        0x0147, // Attack FX E
-        0x0148, // Attack FX C
-        0x0149, // Attack FX F
-        0x014A, // HP Plus
-        0x014B, // HP Plus
-        0x014C, // HP Plus
-        0x014D, // FP Plus
-        0x014E, // FP Plus
-        0x014F, // FP Plus
-        0x0151, // Attack FX F
-        0x0152, // Attack FX F
-        0x0153, // Attack FX F
-    ].into_iter().collect()
-}
+            let json_path = output_path.with_extension(format!("{}.json", name));
+            let json = serde_json::to_string_pretty(&graph).map_err(std::io::Error::from)?;
+            write_atomically(&json_path, json.as_bytes())?;
 
-/// the set from `get_badges_set` includes some badges that were not used in the game.
-/// This contains only the used ones, and duplicates where there were ones in the game.
-fn get_used_badges() -> Vec<u32> {
-    vec![
-        0x00ed,
-        0x00ed,
-        0x012f,
-        0x0124,
-        0x0120,
-        0x0114,
-        0x0121,
-        0x0112,
-        0x0107,
-        0x00e8,
-        0x013a,
-        0x0103,
-        0x011c,
-        0x0148,
-        0x00e5,
-        0x00f7,
-        0x0104,
-        0x0135,
-        0x0109,
-        0x00e4,
-        0x0134,
-        0x011d,
-        0x0146,
-        0x0146,
-        0x0133,
-        0x0133,
-        0x010d,
-        0x0129,
-        0x0111,
-        0x0117,
-        0x0136,
-        0x00e7,
-        0x00f8,
-        0x012e,
-        0x00f9,
-        0x00ec,
-        0x00ef,
-        0x0141,
-        0x0126,
-        0x010c,
-        0x0138,
-        0x0138,
-        0x0147,
-        0x0123,
-        0x0116,
-        0x0113,
-        0x0106,
-        0x00fb,
-        0x00fa,
-        0x0132,
-        0x013c,
-    ]
-}
+            println!("wrote warp graph to {} and {}", dot_path.display(), json_path.display());
+        }
+    }
 
-/// Those badges that are found on the map, (AKA not in Rowf's or Merlow's shops, or through
-/// conversations)
-fn get_map_badges() -> Vec<u32> {
-    vec![
-        0x00ed,
-        0x00ed,
-        0x012f,
-        0x0124,
-        0x0120,
-        0x0114,
-        0x0121,
-        0x0112,
-        0x0107,
-        0x00e8,
-        0x013a,
-        0x0103,
-        0x011c,
-        0x0148,
-        0x00e5,
-        0x00f7,
-        0x0104,
-        0x0135,
-        0x0109,
-        0x00e4,
-        0x0134,
-        0x011d,
-        0x0146,
-        0x0146,
-        0x0133,
-        0x0133,
-        0x010d,
-        0x0129,
-        0x0111,
-        0x0117,
-        0x0136,
-        0x00e7,
-        0x00f8,
-        0x012e,
-        0x00f9,
-        0x00ec,
-        0x00ef,
-        0x0141,
-        0x0126,
-        0x010c,
-        0x0138,
-        0x0138,
-        0x0147,
-        0x0123,
-        0x0116,
-        0x0113,
-        0x0106,
-        0x00fb,
-        0x00fa,
-        0x0132,
-        0x013c,
-    ]
+    Ok(())
 }
 
-/// Those badges that are found in Rowf's shop.
-/// Does not include the I Spy badge which Rowf gives for returning his calculator.
-fn get_rowf_shop_badges() -> Vec<u32> {
-    vec![
-        0x011f,
-        0x00e6,
-        0x00e1,
-        0x00ee,
-        0x00e3,
-        0x00eb,
-        0x00e9,
-        0x010a,
-        0x00e0,
-        0x0143,
-        0x00f4,
-        0x014a,
-        0x014d,
-        0x010b,
-        0x0130,
-        0x0108,
-    ]
+/// Write to a temporary file next to `path` then move it into place, so `path` never holds a
+/// partially written rom.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
 }
 
-/// Those badges that are found in Merlow's shop.
-fn get_merlow_shop_badges() -> Vec<u32> {
-    vec![
-        0x00f3,
-        0x00fc,
-        0x00fe,
-        0x00f1,
-        0x00f2,
-        0x00ff,
-        0x0140,
-        0x0144,
-        0x0100,
-        0x011a,
-        0x011b,
-        0x00f5,
-        0x00fd,
-        0x0105,
-        0x00e2,
-    ]
-}
@@ -0,0 +1,361 @@
+// A short string holding everything needed to make the same rom again: the version of the
+// randomizer, every mode, and the seed.
+
+use std::convert::TryFrom;
+
+use crate::{
+    BadgeSections, ItemMode, PriceMode, PriceRange, RandomizerError, RngVersion, RoomFilter,
+    RoomMode, RoomScope, Settings, StartMode, StartRoom, WarpRules,
+};
+
+/// Bumped whenever the layout below changes.
+const FORMAT: u8 = 1;
+/// Bumped whenever the rom made from the same settings changes, even when neither the layout nor
+/// the version in Cargo.toml does, so that a permalink is only accepted by a randomizer that
+/// makes the same rom from it.
+const ALGORITHM: u8 = 1;
+/// The length without the room filter and the start room, which are only there when they are
+/// not the default or random, and are the only parts that can change length.
+const LENGTH: usize = 39;
+
+/// The same rom can only be expected from the same version of the randomizer.
+fn version() -> [u8; 3] {
+    let mut parts = env!("CARGO_PKG_VERSION")
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0));
+    [
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    ]
+}
+
+/// The settings must have passed `Settings::check`, or counts and lengths may not fit in a byte.
+pub fn encode(settings: &Settings) -> String {
+    let mut bytes = Vec::with_capacity(LENGTH);
+    bytes.push(FORMAT);
+    bytes.extend_from_slice(&version());
+    bytes.push(ALGORITHM);
+
+    bytes.push(match settings.start {
+        StartMode::Standard => 0,
+        StartMode::Quick => 1,
+    });
+    bytes.push(match settings.room_mode {
+        RoomMode::None => 0,
+        RoomMode::StartWithHammer => 1,
+        RoomMode::TotalRandom => 2,
+        RoomMode::Coupled => 3,
+    });
+    let (item_mode, sections) = match settings.item_mode {
+        ItemMode::None => (0, 0),
+        ItemMode::TotalRandom => (1, 0),
+        ItemMode::ShuffleBadgesGlobally => (2, 0),
+        ItemMode::ShuffleBadgesLocally(sections) => (3, sections as u8),
+        ItemMode::DealUsedInto(sections) => (4, sections as u8),
+        ItemMode::DealAllInto(sections) => (5, sections as u8),
+    };
+    bytes.push(item_mode);
+    bytes.push(sections);
+
+    bytes.extend_from_slice(&settings.seed.to_le_bytes());
+
+    bytes.push(match settings.rng_version {
+        RngVersion::Legacy => 0,
+        RngVersion::Uniform => 1,
+    });
+
+    // The ranges are always there, so every permalink of this format is the same length.
+    let (price_mode, [coins, star_pieces]) = match settings.shop_prices {
+        PriceMode::Keep => (0, [PriceRange::DEFAULT_COINS, PriceRange::DEFAULT_STAR_PIECES]),
+        PriceMode::Carry => (1, [PriceRange::DEFAULT_COINS, PriceRange::DEFAULT_STAR_PIECES]),
+        PriceMode::Random { coins, star_pieces } => (2, [coins, star_pieces]),
+    };
+    bytes.push(price_mode);
+    for range in [coins, star_pieces].iter() {
+        bytes.extend_from_slice(&range.min.to_le_bytes());
+        bytes.extend_from_slice(&range.max.to_le_bytes());
+    }
+
+    bytes.push(match settings.room_scope {
+        RoomScope::All => 0,
+        RoomScope::WithinAreas => 1,
+        RoomScope::BetweenAreas => 2,
+        RoomScope::Dungeons => 3,
+    });
+    bytes.push(match settings.warp_rules {
+        WarpRules::Ignore => 0,
+        WarpRules::Respect => 1,
+    });
+    bytes.push(match settings.start_room {
+        StartRoom::Default => 0,
+        StartRoom::Random => 1,
+        StartRoom::Fixed { .. } => 2,
+    });
+
+    // Each pattern list is a count, then each pattern, as a length followed by the pattern.
+    // `RoomFilter::check` makes sure every count and length fits in a byte.
+    if settings.room_filter == RoomFilter::default() {
+        bytes.push(0);
+    } else {
+        bytes.push(1);
+        for patterns in [&settings.room_filter.exclude, &settings.room_filter.include].iter() {
+            bytes.push(patterns.len() as u8);
+            for pattern in patterns.iter() {
+                bytes.push(pattern.len() as u8);
+                bytes.extend_from_slice(pattern.as_bytes());
+            }
+        }
+    }
+
+    // A fixed start room goes last, as its name, written like a pattern, then its entrance.
+    // `Settings::check` makes sure the name is no longer than a pattern.
+    if let StartRoom::Fixed { room, entrance } = &settings.start_room {
+        bytes.push(room.len() as u8);
+        bytes.extend_from_slice(room.as_bytes());
+        bytes.extend_from_slice(&entrance.to_le_bytes());
+    }
+
+    to_base64(&bytes)
+}
+
+pub fn decode(permalink: &str) -> Result<Settings, RandomizerError> {
+    let invalid = |reason: &str| RandomizerError::BadArguments(
+        format!("{:?} is not a valid permalink: {}", permalink, reason)
+    );
+
+    let bytes = from_base64(permalink.trim()).ok_or_else(|| invalid("it contains invalid characters"))?;
+    if bytes.len() < 5 {
+        return Err(invalid("it is the wrong length"));
+    }
+    if bytes[0] != FORMAT || bytes[1..4] != version() || bytes[4] != ALGORITHM {
+        let [major, minor, patch] = version();
+        return Err(RandomizerError::BadArguments(format!(
+            "{:?} was made by version {}.{}.{} of the randomizer, but this is version {}.{}.{}, which makes a different rom from it.",
+            permalink, bytes[1], bytes[2], bytes[3], major, minor, patch
+        )));
+    }
+    if bytes.len() < LENGTH {
+        return Err(invalid("it is the wrong length"));
+    }
+
+    let start = match bytes[5] {
+        0 => StartMode::Standard,
+        1 => StartMode::Quick,
+        _ => return Err(invalid("unknown start mode")),
+    };
+    let room_mode = match bytes[6] {
+        0 => RoomMode::None,
+        1 => RoomMode::StartWithHammer,
+        2 => RoomMode::TotalRandom,
+        3 => RoomMode::Coupled,
+        _ => return Err(invalid("unknown room mode")),
+    };
+    let sections = || BadgeSections::try_from(bytes[8]).map_err(invalid);
+    let item_mode = match bytes[7] {
+        0 => ItemMode::None,
+        1 => ItemMode::TotalRandom,
+        2 => ItemMode::ShuffleBadgesGlobally,
+        3 => ItemMode::ShuffleBadgesLocally(sections()?),
+        4 => ItemMode::DealUsedInto(sections()?),
+        5 => ItemMode::DealAllInto(sections()?),
+        _ => return Err(invalid("unknown item mode")),
+    };
+
+    let mut seed = [0; 16];
+    seed.copy_from_slice(&bytes[9..25]);
+    let seed = u128::from_le_bytes(seed);
+    if seed == 0 {
+        return Err(invalid("the seed is zero"));
+    }
+
+    let rng_version = match bytes[25] {
+        0 => RngVersion::Legacy,
+        1 => RngVersion::Uniform,
+        _ => return Err(invalid("unknown rng version")),
+    };
+
+    let range = |i: usize| PriceRange {
+        min: u16::from_le_bytes([bytes[i], bytes[i + 1]]),
+        max: u16::from_le_bytes([bytes[i + 2], bytes[i + 3]]),
+    };
+    let shop_prices = match bytes[26] {
+        0 => PriceMode::Keep,
+        1 => PriceMode::Carry,
+        2 => PriceMode::Random { coins: range(27), star_pieces: range(31) },
+        _ => return Err(invalid("unknown price mode")),
+    };
+
+    let room_scope = match bytes[35] {
+        0 => RoomScope::All,
+        1 => RoomScope::WithinAreas,
+        2 => RoomScope::BetweenAreas,
+        3 => RoomScope::Dungeons,
+        _ => return Err(invalid("unknown room scope")),
+    };
+    let warp_rules = match bytes[36] {
+        0 => WarpRules::Ignore,
+        1 => WarpRules::Respect,
+        _ => return Err(invalid("unknown warp rules")),
+    };
+
+    let mut rest = &bytes[LENGTH..];
+    let room_filter = match bytes[38] {
+        0 => RoomFilter::default(),
+        1 => {
+            let exclude = read_patterns(&mut rest).ok_or_else(|| invalid("the room filter is cut off"))?;
+            let include = read_patterns(&mut rest).ok_or_else(|| invalid("the room filter is cut off"))?;
+            RoomFilter { exclude, include }
+        },
+        _ => return Err(invalid("unknown room filter")),
+    };
+
+    let start_room = match bytes[37] {
+        0 => StartRoom::Default,
+        1 => StartRoom::Random,
+        2 => {
+            let room = read_text(&mut rest).ok_or_else(|| invalid("the start room is cut off"))?;
+            if rest.len() < 4 {
+                return Err(invalid("the start room is cut off"));
+            }
+            let (entrance, after_entrance) = rest.split_at(4);
+            rest = after_entrance;
+            StartRoom::Fixed {
+                room,
+                entrance: u32::from_le_bytes([entrance[0], entrance[1], entrance[2], entrance[3]]),
+            }
+        },
+        _ => return Err(invalid("unknown start room")),
+    };
+
+    if !rest.is_empty() {
+        return Err(invalid("it is the wrong length"));
+    }
+
+    Ok(Settings {
+        seed,
+        start,
+        start_room,
+        item_mode,
+        room_mode,
+        room_scope,
+        warp_rules,
+        shop_prices,
+        room_filter,
+        rng_version,
+    })
+}
+
+/// Read a list of patterns written by `encode` off the front of `bytes`.
+fn read_patterns(bytes: &mut &[u8]) -> Option<Vec<String>> {
+    let (&count, mut rest) = bytes.split_first()?;
+    let mut patterns = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        patterns.push(read_text(&mut rest)?);
+    }
+    *bytes = rest;
+    Some(patterns)
+}
+
+/// Read a length followed by that many bytes of text off the front of `bytes`.
+fn read_text(bytes: &mut &[u8]) -> Option<String> {
+    let (&length, rest) = bytes.split_first()?;
+    if rest.len() < length as usize {
+        return None;
+    }
+    let (text, rest) = rest.split_at(length as usize);
+    *bytes = rest;
+    String::from_utf8(text.to_vec()).ok()
+}
+
+// The url-safe alphabet, so permalinks survive being pasted into urls and file names.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+        for i in 0..=chunk.len() {
+            output.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    output
+}
+
+fn from_base64(s: &str) -> Option<Vec<u8>> {
+    let digits = s.bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|d| d as u32))
+        .collect::<Option<Vec<u32>>>()?;
+
+    let mut output = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0;
+        for (i, &digit) in chunk.iter().enumerate() {
+            bits |= digit << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            output.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(output)
+}
+
+#[test]
+fn permalinks_round_trip() {
+    let settings = [
+        Settings { seed: 1, ..Settings::default() },
+        Settings { seed: 2, start_room: StartRoom::Random, ..Settings::default() },
+        Settings {
+            seed: u128::MAX,
+            start: StartMode::Quick,
+            start_room: StartRoom::Fixed { room: "kmr_04".to_owned(), entrance: 2 },
+            item_mode: ItemMode::DealAllInto(BadgeSections::MerlowMap),
+            room_mode: RoomMode::Coupled,
+            room_scope: RoomScope::Dungeons,
+            warp_rules: WarpRules::Respect,
+            shop_prices: PriceMode::Random {
+                coins: PriceRange { min: 1, max: 999 },
+                star_pieces: PriceRange { min: 300, max: 301 },
+            },
+            room_filter: RoomFilter {
+                exclude: vec!["tst_*".to_owned(), "mac_05".to_owned()],
+                include: vec!["tst_01".to_owned()],
+            },
+            rng_version: RngVersion::Uniform,
+        },
+        Settings {
+            seed: 0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF,
+            start: StartMode::Standard,
+            start_room: StartRoom::Default,
+            item_mode: ItemMode::ShuffleBadgesLocally(BadgeSections::Rowf),
+            room_mode: RoomMode::None,
+            room_scope: RoomScope::All,
+            warp_rules: WarpRules::Ignore,
+            shop_prices: PriceMode::Keep,
+            room_filter: RoomFilter::default(),
+            rng_version: RngVersion::Legacy,
+        },
+    ];
+    for settings in settings.iter() {
+        let permalink = encode(settings);
+        if settings.room_filter == RoomFilter::default() {
+            assert_eq!(permalink.len(), 52);
+        }
+        assert_eq!(decode(&permalink).unwrap(), *settings);
+    }
+
+    assert!(decode("not a permalink").is_err());
+    assert!(decode(&encode(&settings[0])[..51]).is_err());
+    let custom = encode(&settings[2]);
+    assert!(decode(&custom[..custom.len() - 2]).is_err());
+
+    // a randomizer which would make a different rom does not accept it
+    let mut other = from_base64(&encode(&settings[3])).unwrap();
+    other[4] = ALGORITHM + 1;
+    assert!(decode(&to_base64(&other)).is_err());
+}
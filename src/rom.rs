@@ -0,0 +1,81 @@
+use std::fmt;
+
+/// A whole big-endian rom, held in memory while we patch it.
+pub struct Rom {
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct OutOfRange {
+    pub offset: u32,
+    pub len: usize,
+    pub rom_len: usize,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes at offset {:#x} is out of range of a {:#x} byte rom",
+            self.len, self.offset, self.rom_len
+        )
+    }
+}
+
+impl std::error::Error for OutOfRange {}
+
+impl Rom {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Rom { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn range(&self, offset: u32, len: usize) -> Result<std::ops::Range<usize>, OutOfRange> {
+        let start = offset as usize;
+        match start.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => Ok(start..end),
+            _ => Err(OutOfRange {
+                offset,
+                len,
+                rom_len: self.bytes.len(),
+            }),
+        }
+    }
+
+    pub fn read_bytes(&self, offset: u32, len: usize) -> Result<&[u8], OutOfRange> {
+        let range = self.range(offset, len)?;
+        Ok(&self.bytes[range])
+    }
+
+    pub fn write_bytes(&mut self, offset: u32, data: &[u8]) -> Result<(), OutOfRange> {
+        let range = self.range(offset, data.len())?;
+        self.bytes[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    pub fn read_u32(&self, offset: u32) -> Result<u32, OutOfRange> {
+        let bytes = self.read_bytes(offset, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn write_u32(&mut self, offset: u32, value: u32) -> Result<(), OutOfRange> {
+        self.write_bytes(offset, &value.to_be_bytes())
+    }
+}
+
+#[test]
+fn accesses_past_the_end_are_errors() {
+    let mut rom = Rom::new(vec![0; 8]);
+    rom.write_u32(4, 0xDEAD_BEEF).unwrap();
+    assert_eq!(rom.read_u32(4).unwrap(), 0xDEAD_BEEF);
+    assert!(rom.read_u32(5).is_err());
+    assert!(rom.write_u32(8, 0).is_err());
+    assert!(rom.read_bytes(u32::MAX, 2).is_err());
+}
@@ -0,0 +1,455 @@
+// The warps between rooms, as they are in the unmodified rom, and ways of rearranging them.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::rom::Rom;
+use crate::xs::{xs_choice, xs_shuffle, Xs};
+use crate::{
+    get_room, read_room_table_entry, read_warp, RandomizerError, Room, RoomData, RoomScope, ROOM_COUNT,
+};
+
+/// A warp as it is in the unmodified rom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Warp {
+    pub room: &'static str,
+    pub warp_ptr: u32,
+    /// The position of `warp_ptr` in the room's `warp_ptrs`.
+    pub index: usize,
+    pub target_room: &'static str,
+    pub target_entrance: u32,
+}
+
+/// Read every warp of every room in `room_data`, in room table order. Rooms which appear in
+/// the table more than once are only read the first time.
+pub(crate) fn read_vanilla_warps(rom: &Rom, room_data: &RoomData) -> Result<Vec<Warp>, RandomizerError> {
+    let mut seen = HashSet::with_capacity(ROOM_COUNT as usize);
+    let mut warps = Vec::new();
+    for i in 0..ROOM_COUNT {
+        let (name, room_ptr) = read_room_table_entry(rom, i)?;
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+
+        let (room, data) = get_room_with_name(room_data, &name)?;
+
+        for (index, &warp_ptr) in data.warp_ptrs.iter().enumerate() {
+            let (target_room, target_entrance) = read_warp(rom, room_ptr, warp_ptr)?;
+            let (target_room, _) = get_room_with_name(room_data, &target_room)?;
+            warps.push(Warp { room, warp_ptr, index, target_room, target_entrance });
+        }
+    }
+
+    Ok(warps)
+}
+
+/// `get_room`, along with the `'static` name of the room.
+pub(crate) fn get_room_with_name<'a>(
+    room_data: &'a RoomData,
+    name: &str,
+) -> Result<(&'static str, &'a Room), RandomizerError> {
+    room_data.get_key_value(name)
+        .map(|(&name, room)| (name, room))
+        .ok_or_else(|| RandomizerError::MissingRoomData(format!("no entry for room {:?}", name)))
+}
+
+/// What room randomization needs to know about the warps, to avoid leaving the player stuck.
+pub(crate) struct WarpInfo {
+    /// Rooms with one warp, or none.
+    dead_ends: HashSet<&'static str>,
+    one_way: HashSet<(&'static str, u32)>,
+    /// Rooms with at least one warp that is not one-way.
+    way_back: HashSet<&'static str>,
+}
+
+impl WarpInfo {
+    /// roomdata.json does not say what kind of warp each warp is, so a warp is taken to be
+    /// one-way, like a fall, when no warp in `warps` leads back the other way.
+    pub(crate) fn new(warps: &[Warp], room_data: &RoomData) -> Self {
+        let connections: HashSet<(&str, &str)> = warps.iter()
+            .map(|warp| (warp.room, warp.target_room))
+            .collect();
+
+        let mut one_way = HashSet::new();
+        let mut way_back = HashSet::new();
+        for warp in warps.iter() {
+            if !connections.contains(&(warp.target_room, warp.room)) {
+                one_way.insert((warp.room, warp.warp_ptr));
+            } else {
+                way_back.insert(warp.room);
+            }
+        }
+
+        WarpInfo {
+            dead_ends: room_data.iter()
+                .filter(|(_, room)| room.warp_ptrs.len() <= 1)
+                .map(|(&name, _)| name)
+                .collect(),
+            one_way,
+            way_back,
+        }
+    }
+
+    pub(crate) fn is_dead_end(&self, room: &str) -> bool {
+        self.dead_ends.contains(room)
+    }
+
+    /// Whether the warp `key` may lead into `room`. Dead ends only connect to rooms that are
+    /// not dead ends, so they cannot be chained together, and one-way warps only lead to rooms
+    /// with a way back.
+    pub(crate) fn may_lead_to(&self, key: (&str, u32), room: &str) -> bool {
+        !(self.is_dead_end(key.0) && self.is_dead_end(room))
+            && (!self.one_way.contains(&key) || self.way_back.contains(room))
+    }
+}
+
+/// The areas which are dungeons: Koopa Bros. Fortress, Dry Dry Ruins, Tubba Blubba's Castle,
+/// Shy Guy's Toy Box, Mt. Lavalava, Crystal Palace and Bowser's Castle.
+const DUNGEON_AREAS: [&str; 7] = ["trd", "isk", "dgb", "omo", "kzn", "pra", "kpa"];
+
+/// The area a room is in, which is the first three letters of its name.
+pub(crate) fn area(room: &'static str) -> &'static str {
+    room.get(..3).unwrap_or(room)
+}
+
+/// Warps are only ever changed to lead where other warps of the same group lead. This is the
+/// group of a warp from `room` to `target_room` in the unmodified rom, or `None` if `scope`
+/// says the warp should not be changed at all.
+pub(crate) fn warp_group(
+    scope: RoomScope,
+    room: &'static str,
+    target_room: &'static str,
+) -> Option<&'static str> {
+    let same_area = area(room) == area(target_room);
+    match scope {
+        RoomScope::All => Some(""),
+        RoomScope::WithinAreas if same_area => Some(area(room)),
+        RoomScope::BetweenAreas if !same_area => Some(""),
+        RoomScope::Dungeons if same_area && DUNGEON_AREAS.contains(&area(room)) => Some(area(room)),
+        RoomScope::WithinAreas | RoomScope::BetweenAreas | RoomScope::Dungeons => None,
+    }
+}
+
+/// One side of a two-way connection between rooms: a warp, and the entrance that the warp on
+/// the other side leads to, which is where you arrive when coming through this door.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Door {
+    pub room: &'static str,
+    pub warp_ptr: u32,
+    pub entrance: u32,
+}
+
+/// Pair each warp with the warp that leads back through it, considering only warps between
+/// rooms in `allowed`. Warps without a way back are left out.
+///
+/// roomdata.json does not say which entrance a warp is next to, so when a room has more than
+/// one warp to the same room we guess, by assuming that warps and entrances are listed in
+/// roughly the same order.
+pub(crate) fn pair_warps(
+    warps: &[Warp],
+    room_data: &RoomData,
+    allowed: &HashSet<&str>,
+) -> Result<Vec<(Door, Door)>, RandomizerError> {
+    let mut paired = vec![false; warps.len()];
+    let mut pairs = Vec::new();
+
+    for (i, warp) in warps.iter().enumerate() {
+        if paired[i]
+            || !allowed.contains(warp.room)
+            || !allowed.contains(warp.target_room)
+        {
+            continue;
+        }
+        let target = get_room(room_data, warp.target_room)?;
+        let entrance_index = target.entrances.iter()
+            .position(|&e| e == warp.target_entrance)
+            .unwrap_or(0);
+
+        let reverse = warps.iter()
+            .enumerate()
+            .filter(|&(j, other)| {
+                j != i
+                    && !paired[j]
+                    && other.room == warp.target_room
+                    && other.target_room == warp.room
+            })
+            .min_by_key(|(_, other)| {
+                (other.index as isize - entrance_index as isize).abs()
+            })
+            .map(|(j, _)| j);
+
+        if let Some(j) = reverse {
+            paired[i] = true;
+            paired[j] = true;
+            let other = &warps[j];
+            pairs.push((
+                Door { room: warp.room, warp_ptr: warp.warp_ptr, entrance: other.target_entrance },
+                Door { room: other.room, warp_ptr: other.warp_ptr, entrance: warp.target_entrance },
+            ));
+        }
+    }
+
+    Ok(pairs)
+}
+
+/// Where each warp, identified by its room name and pointer, should lead.
+pub(crate) type Targets = HashMap<(&'static str, u32), (&'static str, u32)>;
+
+/// Shuffle the doors of `pairs` and join them up two at a time, so that going through a door
+/// and then back through the door you arrived at returns you to where you started.
+pub(crate) fn shuffle_coupled(xs: &mut Xs, pairs: &[(Door, Door)]) -> Vec<(Door, Door)> {
+    let doors: Vec<Door> = pairs.iter().flat_map(|&(a, b)| vec![a, b]).collect();
+
+    let mut order: Vec<u32> = (0..doors.len() as u32).collect();
+    xs_shuffle(xs, &mut order);
+
+    order.chunks(2)
+        .filter_map(|chunk| match *chunk {
+            [a, b] => Some((doors[a as usize], doors[b as usize])),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `shuffle_coupled`, but only joining doors from pairs of the same group, as given by
+/// `warp_group`. Pairs without a group are left out. Returns the group of each new pair too.
+pub(crate) fn shuffle_coupled_in_groups(
+    xs: &mut Xs,
+    pairs: &[(Door, Door)],
+    scope: RoomScope,
+) -> (Vec<(Door, Door)>, Vec<&'static str>) {
+    let mut grouped: Vec<(&'static str, Vec<(Door, Door)>)> = Vec::new();
+    for &(a, b) in pairs.iter() {
+        if let Some(group) = warp_group(scope, a.room, b.room) {
+            match grouped.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, pairs)) => pairs.push((a, b)),
+                None => grouped.push((group, vec![(a, b)])),
+            }
+        }
+    }
+
+    let mut shuffled = Vec::with_capacity(pairs.len());
+    let mut groups = Vec::with_capacity(pairs.len());
+    for (group, pairs) in grouped {
+        let new_pairs = shuffle_coupled(xs, &pairs);
+        groups.extend(std::iter::repeat_n(group, new_pairs.len()));
+        shuffled.extend(new_pairs);
+    }
+    (shuffled, groups)
+}
+
+/// Shuffle where the warps of each group, as given by `warp_group`, lead, among the places that
+/// warps of that group lead in the unmodified rom. Only warps between rooms in `allowed` are
+/// changed. Unlike picking a random place for each warp, this leaves every room with as many
+/// ways in as it had, which keeps the rooms of small groups connected far more often.
+pub(crate) fn shuffle_targets_in_groups(
+    xs: &mut Xs,
+    warps: &[Warp],
+    scope: RoomScope,
+    allowed: &HashSet<&str>,
+) -> Targets {
+    let mut grouped: Vec<(&'static str, Vec<&Warp>)> = Vec::new();
+    for warp in warps.iter() {
+        if !allowed.contains(warp.room) || !allowed.contains(warp.target_room) {
+            continue;
+        }
+        if let Some(group) = warp_group(scope, warp.room, warp.target_room) {
+            match grouped.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, warps)) => warps.push(warp),
+                None => grouped.push((group, vec![warp])),
+            }
+        }
+    }
+
+    let mut targets = Targets::with_capacity(warps.len());
+    for (_, warps) in grouped {
+        let mut order: Vec<u32> = (0..warps.len() as u32).collect();
+        xs_shuffle(xs, &mut order);
+        for (warp, &i) in warps.iter().zip(order.iter()) {
+            let target = warps[i as usize];
+            targets.insert((warp.room, warp.warp_ptr), (target.target_room, target.target_entrance));
+        }
+    }
+    targets
+}
+
+/// Rejoin each pair of doors which are both in dead ends, which would leave two rooms that
+/// nothing else leads to, with a randomly chosen pair of doors which are in neither. Only pairs
+/// with the same entry in `groups` are rejoined.
+pub(crate) fn separate_dead_ends(
+    xs: &mut Xs,
+    pairs: &mut [(Door, Door)],
+    groups: &[&'static str],
+    info: &WarpInfo,
+) {
+    for i in 0..pairs.len() {
+        let (a, b) = pairs[i];
+        if !info.is_dead_end(a.room) || !info.is_dead_end(b.room) {
+            continue;
+        }
+        let others: Vec<u32> = (0..pairs.len() as u32)
+            .filter(|&j| {
+                let (c, d) = pairs[j as usize];
+                groups[j as usize] == groups[i] && !info.is_dead_end(c.room) && !info.is_dead_end(d.room)
+            })
+            .collect();
+        if others.is_empty() {
+            continue;
+        }
+        let j = xs_choice(xs, &others) as usize;
+        let (c, d) = pairs[j];
+        pairs[i] = (a, c);
+        pairs[j] = (b, d);
+    }
+}
+
+/// Make each door of each pair lead to the other.
+pub(crate) fn coupled_targets(pairs: &[(Door, Door)]) -> Targets {
+    let mut targets = Targets::with_capacity(pairs.len() * 2);
+    for &(a, b) in pairs.iter() {
+        targets.insert((a.room, a.warp_ptr), (b.room, b.entrance));
+        targets.insert((b.room, b.warp_ptr), (a.room, a.entrance));
+    }
+    targets
+}
+
+#[test]
+fn coupled_shuffles_can_be_walked_back() {
+    fn room(entrances: Vec<u32>, warp_ptrs: Vec<u32>) -> crate::Room {
+        crate::Room { entrances, items: vec![], item_needs: vec![], warp_ptrs }
+    }
+    let mut room_data = RoomData::new();
+    room_data.insert("aaa_00", room(vec![0, 1], vec![10, 11]));
+    room_data.insert("aaa_01", room(vec![0, 1], vec![20, 21]));
+    room_data.insert("aaa_02", room(vec![0], vec![30]));
+
+    let warp = |room, warp_ptr, index, target_room, target_entrance| Warp {
+        room,
+        warp_ptr,
+        index,
+        target_room,
+        target_entrance,
+    };
+    let warps = vec![
+        warp("aaa_00", 10, 0, "aaa_01", 0),
+        warp("aaa_00", 11, 1, "aaa_01", 1),
+        warp("aaa_01", 20, 0, "aaa_00", 0),
+        warp("aaa_01", 21, 1, "aaa_00", 1),
+        // one way
+        warp("aaa_02", 30, 0, "aaa_00", 0),
+    ];
+    let allowed = room_data.keys().copied().collect();
+
+    let pairs = pair_warps(&warps, &room_data, &allowed).unwrap();
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs[0].0, Door { room: "aaa_00", warp_ptr: 10, entrance: 0 });
+    assert_eq!(pairs[0].1, Door { room: "aaa_01", warp_ptr: 20, entrance: 0 });
+
+    let doors: Vec<Door> = pairs.iter().flat_map(|&(a, b)| vec![a, b]).collect();
+    for seed in 1..20 {
+        let xs = &mut crate::xs::xs_from_seed(seed, crate::RngVersion::Uniform);
+        let targets = coupled_targets(&shuffle_coupled(xs, &pairs));
+        assert_eq!(targets.len(), doors.len());
+        for door in doors.iter() {
+            let (room, entrance) = targets[&(door.room, door.warp_ptr)];
+            let back = doors.iter()
+                .find(|d| d.room == room && d.entrance == entrance)
+                .unwrap();
+            assert_eq!(targets[&(back.room, back.warp_ptr)], (door.room, door.entrance));
+        }
+    }
+}
+
+#[test]
+fn grouped_shuffles_stay_in_their_areas() {
+    let warp = |room, warp_ptr, target_room| Warp {
+        room,
+        warp_ptr,
+        index: 0,
+        target_room,
+        target_entrance: 0,
+    };
+    let warps = vec![
+        warp("aaa_00", 1, "aaa_01"),
+        warp("aaa_01", 1, "aaa_02"),
+        warp("aaa_02", 1, "aaa_00"),
+        warp("aaa_02", 2, "kpa_00"),
+        warp("kpa_00", 1, "aaa_02"),
+        warp("kpa_00", 2, "kpa_01"),
+        warp("kpa_01", 1, "kpa_00"),
+    ];
+    let allowed = warps.iter().map(|warp| warp.room).collect();
+    let door = |room| Door { room, warp_ptr: 1, entrance: 0 };
+
+    for seed in 1..20 {
+        let xs = &mut crate::xs::xs_from_seed(seed, crate::RngVersion::Uniform);
+
+        let targets = shuffle_targets_in_groups(xs, &warps, RoomScope::WithinAreas, &allowed);
+        assert_eq!(targets.len(), 5);
+        for (&(room, _), &(target, _)) in targets.iter() {
+            assert_eq!(area(room), area(target));
+        }
+
+        let targets = shuffle_targets_in_groups(xs, &warps, RoomScope::Dungeons, &allowed);
+        let mut changed: Vec<_> = targets.keys().copied().collect();
+        changed.sort();
+        assert_eq!(changed, vec![("kpa_00", 2), ("kpa_01", 1)]);
+
+        let pairs = [
+            (door("aaa_00"), door("aaa_01")),
+            (door("aaa_02"), door("kpa_00")),
+            (door("kpa_01"), door("kpa_02")),
+            (door("kpa_03"), door("kpa_04")),
+        ];
+        let (shuffled, groups) = shuffle_coupled_in_groups(xs, &pairs, RoomScope::BetweenAreas);
+        assert_eq!(shuffled.len(), 1);
+        assert_eq!(groups, vec![""]);
+    }
+}
+
+#[test]
+fn dead_ends_and_one_way_warps_are_kept_apart() {
+    fn room(warp_ptrs: Vec<u32>) -> crate::Room {
+        crate::Room { entrances: vec![0], items: vec![], item_needs: vec![], warp_ptrs }
+    }
+    let mut room_data = RoomData::new();
+    room_data.insert("aaa_00", room(vec![10, 11]));
+    room_data.insert("aaa_01", room(vec![20]));
+    room_data.insert("aaa_02", room(vec![30]));
+    room_data.insert("aaa_03", room(vec![]));
+
+    let warp = |room, warp_ptr, target_room| Warp {
+        room,
+        warp_ptr,
+        index: 0,
+        target_room,
+        target_entrance: 0,
+    };
+    let warps = vec![
+        warp("aaa_00", 10, "aaa_01"),
+        // one way, since nothing leads back
+        warp("aaa_00", 11, "aaa_03"),
+        warp("aaa_01", 20, "aaa_00"),
+        warp("aaa_02", 30, "aaa_00"),
+    ];
+    let info = WarpInfo::new(&warps, &room_data);
+
+    assert!(info.may_lead_to(("aaa_01", 20), "aaa_00"));
+    assert!(!info.may_lead_to(("aaa_01", 20), "aaa_02"));
+    assert!(info.may_lead_to(("aaa_00", 11), "aaa_01"));
+    assert!(!info.may_lead_to(("aaa_00", 11), "aaa_02"));
+    assert!(!info.may_lead_to(("aaa_00", 11), "aaa_03"));
+    assert!(info.may_lead_to(("aaa_00", 10), "aaa_03"));
+
+    let door = |room, warp_ptr| Door { room, warp_ptr, entrance: 0 };
+    for seed in 1..20 {
+        let xs = &mut crate::xs::xs_from_seed(seed, crate::RngVersion::Uniform);
+        let mut pairs = [
+            (door("aaa_01", 20), door("aaa_02", 30)),
+            (door("aaa_00", 10), door("aaa_00", 11)),
+        ];
+        separate_dead_ends(xs, &mut pairs, &["", ""], &info);
+        for (a, b) in pairs.iter() {
+            assert!(!info.is_dead_end(a.room) || !info.is_dead_end(b.room), "{:?}", pairs);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// A short fingerprint of the settings and seed, so racers can check that they all made the
+// same rom without comparing the roms themselves.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::items::{get_items, ItemCategory};
+use crate::{permalink, verify, Settings};
+
+/// Where the hash is written into the rom: the end of the internal name in the header, after
+/// "PAPER MARIO ". The header is not covered by the checksum. This is not shown in the game;
+/// that would need the hash written into the file select text instead.
+pub(crate) const HEADER_OFFSET: u32 = 0x2C;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SeedHash {
+    /// Eight hex digits, as written into the rom header.
+    pub code: String,
+    /// The same hash as item names, which are easier to read out loud.
+    pub items: [&'static str; 4],
+}
+
+pub fn seed_hash(settings: &Settings) -> SeedHash {
+    // The permalink includes the version and the algorithm, which also change what rom comes out.
+    let digest = verify::sha1(permalink::encode(settings).as_bytes());
+
+    let code = digest[..4].iter().map(|b| format!("{:02X}", b)).collect();
+
+    let names: Vec<&'static str> = get_items()
+        .iter()
+        .filter(|item| item.category == ItemCategory::Consumable || item.category == ItemCategory::Badge)
+        .map(|item| item.name)
+        .collect();
+    let mut items = [""; 4];
+    for (i, item) in items.iter_mut().enumerate() {
+        let index = u16::from_be_bytes([digest[4 + i * 2], digest[5 + i * 2]]) as usize;
+        *item = names[index % names.len()];
+    }
+
+    SeedHash { code, items }
+}
+
+impl fmt::Display for SeedHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.code, self.items.join(", "))
+    }
+}
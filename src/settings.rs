@@ -0,0 +1,418 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::RandomizerError;
+
+macro_rules! d {
+    (for $type: ty : $value: expr) => {
+        impl Default for $type {
+            fn default() -> Self {
+                $value
+            }
+        }
+    };
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoomMode {
+    None,
+    StartWithHammer,
+    TotalRandom,
+    /// Shuffle the two-way connections between rooms, so going back through a door leads back
+    /// to where you came from.
+    Coupled,
+}
+d!(for RoomMode : RoomMode::StartWithHammer);
+
+/// Which warps room randomization changes. Rooms are grouped into areas by the first three
+/// letters of their names, like `kmr` for Goomba Village or `kpa` for Bowser's Castle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoomScope {
+    /// Any warp can lead anywhere.
+    All,
+    /// Only warps between rooms of the same area are changed, and only to other rooms of that
+    /// area.
+    WithinAreas,
+    /// Only warps from one area to another are changed, so the rooms inside each area stay
+    /// connected as they were.
+    BetweenAreas,
+    /// Like `WithinAreas`, but only in the dungeons.
+    Dungeons,
+}
+d!(for RoomScope : RoomScope::All);
+
+/// Whether room randomization avoids connections that can leave the player stuck: a dead end,
+/// (a room with one warp,) leading only into another dead end, or a one-way warp, like a fall,
+/// leading into a room with no way back out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarpRules {
+    Ignore,
+    Respect,
+}
+d!(for WarpRules : WarpRules::Respect);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartMode {
+    Standard,
+    Quick
+}
+d!(for StartMode : StartMode::Standard);
+
+/// Where the player ends up when leaving the room the game starts in, `kmr_00`, which is right
+/// away with `StartMode::Quick`, or after the prologue otherwise.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartRoom {
+    /// By the hammer with `RoomMode::StartWithHammer`, and where the game puts the player with
+    /// any other room mode.
+    Default,
+    Fixed { room: String, entrance: u32 },
+    /// A random room that room randomization can use.
+    Random,
+}
+d!(for StartRoom : StartRoom::Default);
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BadgeSections {
+    Map = 0b001,
+    Rowf = 0b010,
+    RowfMap = 0b011,
+    Merlow = 0b100,
+    MerlowMap = 0b101,
+    MerlowRowf = 0b110,
+    MerlowRowfMap = 0b111,
+}
+
+impl BadgeSections {
+    // if `other` contains two or more set bits, this is true only if all of those bits are set
+    // in `self`.
+    pub fn contains(self, other: Self) -> bool {
+        self as u8 & other as u8 == other as u8
+    }
+}
+
+impl TryFrom<u8> for BadgeSections {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use BadgeSections::*;
+        match value {
+             0b001 => Ok(Map),
+             0b010 => Ok(Rowf),
+             0b011 => Ok(RowfMap),
+             0b100 => Ok(Merlow),
+             0b101 => Ok(MerlowMap),
+             0b110 => Ok(MerlowRowf),
+             0b111 => Ok(MerlowRowfMap),
+            _ => Err("Invalid BadgeSections")
+        }
+    }
+}
+
+impl std::ops::BitOr for BadgeSections {
+    type Output = BadgeSections;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        // Every variant has at least one bit set, so the union of two of them is never the
+        // only invalid value, zero.
+        BadgeSections::try_from(self as u8 | rhs as u8).unwrap_or(self)
+    }
+}
+
+impl std::ops::BitOrAssign for BadgeSections {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemMode {
+    None,
+    TotalRandom,
+    ShuffleBadgesGlobally,
+    ShuffleBadgesLocally(BadgeSections),
+    DealUsedInto(BadgeSections),
+    DealAllInto(BadgeSections),
+}
+d!(for ItemMode : ItemMode::None);
+
+/// An inclusive range of prices, in whatever Rowf or Merlow take.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl PriceRange {
+    /// The game does not show prices with more than three digits.
+    pub const MAX_PRICE: u16 = 999;
+    pub const DEFAULT_COINS: PriceRange = PriceRange { min: 25, max: 250 };
+    pub const DEFAULT_STAR_PIECES: PriceRange = PriceRange { min: 1, max: 10 };
+}
+
+/// What Rowf and Merlow charge for the badges they end up selling. Rowf takes coins and Merlow
+/// takes star pieces.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceMode {
+    /// Each slot keeps its price, whatever badge ends up in it.
+    Keep,
+    /// A badge costs what that shop charged for it in the unmodified game. One the shop did not
+    /// sell costs what the other shop charged, scaled to this shop's usual prices, or if neither
+    /// sold it, this shop's usual price.
+    Carry,
+    Random { coins: PriceRange, star_pieces: PriceRange },
+}
+d!(for PriceMode : PriceMode::Keep);
+
+/// The rooms that are left out of room randomization unless told otherwise, because entering
+/// them out of order crashes the game or leaves the player stuck.
+pub const DEFAULT_EXCLUDED_ROOMS: [&str; 12] = [
+    "end_00", "end_01", "gv_01", "mgm_03", "tst_11", "tst_12", "tst_13", "tst_20",
+    "hos_04", "hos_05", "hos_10", "mac_05",
+];
+
+/// Which rooms room randomization can send warps into. Each pattern is either a room name, or
+/// the start of room names followed by `*`, like `tst_*`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomFilter {
+    pub exclude: Vec<String>,
+    /// Rooms to use even though they match a pattern in `exclude`.
+    pub include: Vec<String>,
+}
+d!(for RoomFilter : RoomFilter {
+    exclude: DEFAULT_EXCLUDED_ROOMS.iter().map(|&name| name.to_owned()).collect(),
+    include: Vec::new(),
+});
+
+/// Whether a room can be used, and which pattern decided that, if any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RoomEligibility {
+    Eligible,
+    Excluded(String),
+    Included(String),
+}
+
+impl RoomEligibility {
+    pub fn is_eligible(&self) -> bool {
+        match self {
+            RoomEligibility::Eligible | RoomEligibility::Included(_) => true,
+            RoomEligibility::Excluded(_) => false,
+        }
+    }
+}
+
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+impl RoomFilter {
+    /// Room names are at most eight bytes, so patterns longer than that cannot match anything.
+    pub const MAX_PATTERN_LENGTH: usize = 8;
+    /// There is no need for more patterns than this in either list, since patterns can match
+    /// many rooms.
+    pub const MAX_PATTERNS: usize = 255;
+
+    /// Patterns must not be empty, and can only have a `*` on the end.
+    pub fn check(&self) -> Result<(), RandomizerError> {
+        if self.exclude.len() > Self::MAX_PATTERNS || self.include.len() > Self::MAX_PATTERNS {
+            return Err(RandomizerError::BadArguments(format!(
+                "At most {} rooms can be excluded or included. Try using patterns like tst_* to match more than one room.",
+                Self::MAX_PATTERNS
+            )));
+        }
+        for pattern in self.exclude.iter().chain(self.include.iter()) {
+            let name = pattern.strip_suffix('*').unwrap_or(pattern);
+            if name.is_empty()
+                || name.len() > Self::MAX_PATTERN_LENGTH
+                || name.contains('*')
+                || name.contains(',')
+            {
+                return Err(RandomizerError::BadArguments(format!(
+                    "{:?} is not a room pattern. Patterns are room names, like mac_05, or the start of room names followed by a *, like tst_*",
+                    pattern
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn eligibility(&self, name: &str) -> RoomEligibility {
+        let find = |patterns: &[String]| patterns.iter().find(|p| pattern_matches(p, name)).cloned();
+        match (find(&self.exclude), find(&self.include)) {
+            (None, _) => RoomEligibility::Eligible,
+            (Some(_), Some(include)) => RoomEligibility::Included(include),
+            (Some(exclude), None) => RoomEligibility::Excluded(exclude),
+        }
+    }
+}
+
+/// Which version of the random number helpers to use. The first version was biased, and did
+/// not shuffle properly. It is kept as an option, but it does not make the roms of older versions
+/// of the randomizer again, since much else about what they did has changed since.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RngVersion {
+    Legacy,
+    Uniform,
+}
+d!(for RngVersion : RngVersion::Uniform);
+
+/// Everything that determines the output of `randomize`, given the same input rom.
+///
+/// Fields missing from serialized settings are given their default values, so settings files
+/// only need to mention what they change.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Must not be zero.
+    pub seed: u128,
+    pub start: StartMode,
+    pub start_room: StartRoom,
+    pub item_mode: ItemMode,
+    pub room_mode: RoomMode,
+    pub room_scope: RoomScope,
+    pub warp_rules: WarpRules,
+    pub shop_prices: PriceMode,
+    pub room_filter: RoomFilter,
+    pub rng_version: RngVersion,
+}
+
+impl Settings {
+    /// Check the settings that no rom is needed to check. Settings read from a file have not
+    /// been through the flag checks, and this must pass before they are put in a permalink or a
+    /// seed hash, since those only have a byte for each count and length.
+    pub fn check(&self) -> Result<(), RandomizerError> {
+        self.room_filter.check()?;
+        if self.room_mode == RoomMode::None && self.room_scope != RoomScope::All {
+            return Err(RandomizerError::BadArguments(
+                "A room scope cannot be used when rooms are not randomized.".to_owned()
+            ));
+        }
+        if let StartRoom::Fixed { room, .. } = &self.start_room {
+            // no room has a longer name
+            if room.len() > RoomFilter::MAX_PATTERN_LENGTH {
+                return Err(RandomizerError::BadArguments(format!("There is no room named {:?} to start in.", room)));
+            }
+        }
+        if let PriceMode::Random { coins, star_pieces } = self.shop_prices {
+            for (range, currency) in [(coins, "coins"), (star_pieces, "star pieces")].iter() {
+                if range.min > range.max || range.max > PriceRange::MAX_PRICE {
+                    return Err(RandomizerError::BadArguments(format!(
+                        "{}-{} is not a valid range of prices in {}. The lowest price must not be more than the highest, which must not be more than {}.",
+                        range.min, range.max, currency, PriceRange::MAX_PRICE
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turn what was passed as a seed into a seed. Positive numbers are used as they are, and
+/// anything else, like "weekly-race-42", is hashed. The hash must never change, or the same
+/// text would stop giving the same rom.
+pub fn seed_from_str(s: &str) -> u128 {
+    match s.parse() {
+        Ok(seed) if seed != 0 => seed,
+        _ => {
+            let digest = crate::verify::sha1(s.as_bytes());
+            let mut bytes = [0; 16];
+            bytes.copy_from_slice(&digest[..16]);
+            // zero is not a legal xor_shift seed
+            u128::from_be_bytes(bytes).max(1)
+        }
+    }
+}
+
+pub const PRESET_NAMES: [&str; 3] = ["beginner", "badge-chaos", "full-rooms"];
+
+/// Named settings, for those who would rather not pick every mode themselves. The seed is left
+/// as zero, to be filled in by the caller.
+pub fn preset(name: &str) -> Option<Settings> {
+    let (start, item_mode, room_mode) = match name {
+        "beginner" => (
+            StartMode::Quick,
+            ItemMode::ShuffleBadgesLocally(BadgeSections::Map),
+            RoomMode::None,
+        ),
+        "badge-chaos" => (
+            StartMode::Quick,
+            ItemMode::DealAllInto(BadgeSections::MerlowRowfMap),
+            RoomMode::None,
+        ),
+        "full-rooms" => (
+            StartMode::Quick,
+            ItemMode::TotalRandom,
+            RoomMode::Coupled,
+        ),
+        _ => return None,
+    };
+
+    Some(Settings {
+        seed: 0,
+        start,
+        start_room: StartRoom::Default,
+        item_mode,
+        room_mode,
+        room_scope: RoomScope::All,
+        warp_rules: WarpRules::default(),
+        shop_prices: PriceMode::Keep,
+        room_filter: RoomFilter::default(),
+        rng_version: RngVersion::default(),
+    })
+}
+
+#[test]
+fn text_seeds_hash_to_the_same_number_every_time() {
+    assert_eq!(seed_from_str("42"), 42);
+    assert_eq!(seed_from_str("weekly-race-42"), 218280179391734676648883482894223480333);
+    assert_ne!(seed_from_str("0"), 0);
+}
+
+#[test]
+fn room_patterns_match_names_and_prefixes() {
+    let filter = RoomFilter {
+        exclude: vec!["tst_*".to_owned(), "mac_05".to_owned()],
+        include: vec!["tst_02".to_owned()],
+    };
+    filter.check().unwrap();
+    assert_eq!(filter.eligibility("tst_01"), RoomEligibility::Excluded("tst_*".to_owned()));
+    assert_eq!(filter.eligibility("tst_02"), RoomEligibility::Included("tst_02".to_owned()));
+    assert_eq!(filter.eligibility("mac_05"), RoomEligibility::Excluded("mac_05".to_owned()));
+    assert_eq!(filter.eligibility("mac_050"), RoomEligibility::Eligible);
+
+    for bad in ["", "*", "t*t_01", "tst_**", "much_too_long"].iter() {
+        let filter = RoomFilter { exclude: vec![bad.to_string()], include: Vec::new() };
+        assert!(filter.check().is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn presets_survive_a_round_trip_through_json() {
+    for name in PRESET_NAMES.iter() {
+        let settings = Settings { seed: u128::MAX, ..preset(name).unwrap() };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+    }
+
+    let partial: Settings = serde_json::from_str(r#"{"room_mode": "Coupled"}"#).unwrap();
+    assert_eq!(partial, Settings {
+        room_mode: RoomMode::Coupled,
+        ..Settings::default()
+    });
+}
+
+#[test]
+fn settings_that_would_not_fit_a_permalink_are_rejected() {
+    let mut settings = preset("beginner").unwrap();
+    assert!(settings.check().is_ok());
+    settings.start_room = StartRoom::Fixed { room: "a".repeat(300), entrance: 0 };
+    assert!(settings.check().is_err());
+    settings.start_room = StartRoom::Default;
+    settings.room_filter.exclude = vec!["kmr_00".to_owned(); RoomFilter::MAX_PATTERNS + 1];
+    assert!(settings.check().is_err());
+}
@@ -0,0 +1,185 @@
+// A record of what the randomizer did, read back out of the patched rom so that it describes
+// what is actually in there.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::badges::{get_merlow_iter, get_rowf_iter};
+use crate::items::get_item_name;
+use crate::rom::Rom;
+use crate::seed_hash::SeedHash;
+use crate::{permalink, read_room_table_entry, read_warp, room_offset, RandomizerError, RoomData, Settings, ROOM_COUNT};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Spoiler {
+    pub seed: u128,
+    pub hash: SeedHash,
+    /// Makes the same rom again, with the same version of the randomizer.
+    pub permalink: String,
+    pub settings: Settings,
+    pub rooms: Vec<RoomSpoiler>,
+    pub rowf: Vec<ItemSpoiler>,
+    pub merlow: Vec<ItemSpoiler>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoomSpoiler {
+    pub name: String,
+    pub warps: Vec<WarpSpoiler>,
+    pub items: Vec<ItemSpoiler>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WarpSpoiler {
+    pub warp_ptr: u32,
+    pub original_room: String,
+    pub original_entrance: u32,
+    pub room: String,
+    pub entrance: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ItemSpoiler {
+    /// A pointer into the room for map items, or a rom offset for shop slots.
+    pub ptr: u32,
+    pub original_id: u32,
+    pub original: String,
+    pub id: u32,
+    pub name: String,
+    /// Only shop slots have prices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_price: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<u32>,
+}
+
+impl ItemSpoiler {
+    fn new(ptr: u32, original_id: u32, id: u32) -> Self {
+        ItemSpoiler {
+            ptr,
+            original_id,
+            original: get_item_name(original_id).to_owned(),
+            id,
+            name: get_item_name(id).to_owned(),
+            original_price: None,
+            price: None,
+        }
+    }
+}
+
+pub(crate) fn read(
+    original: &Rom,
+    patched: &Rom,
+    room_data: &RoomData,
+    hash: SeedHash,
+    settings: &Settings,
+) -> Result<Spoiler, RandomizerError> {
+    let mut rooms = Vec::with_capacity(ROOM_COUNT as usize);
+    for i in 0..ROOM_COUNT {
+        let (name, room_ptr) = read_room_table_entry(patched, i)?;
+        let room = crate::get_room(room_data, &name)?;
+
+        let mut warps = Vec::with_capacity(room.warp_ptrs.len());
+        for &warp_ptr in room.warp_ptrs.iter() {
+            let (original_room, original_entrance) = read_warp(original, room_ptr, warp_ptr)?;
+            let (new_room, entrance) = read_warp(patched, room_ptr, warp_ptr)?;
+            warps.push(WarpSpoiler {
+                warp_ptr,
+                original_room,
+                original_entrance,
+                room: new_room,
+                entrance,
+            });
+        }
+
+        let mut items = Vec::with_capacity(room.items.len());
+        for &item_ptr in room.items.iter() {
+            let offset = room_offset(room_ptr, item_ptr);
+            items.push(ItemSpoiler::new(
+                item_ptr,
+                original.read_u32(offset)?,
+                patched.read_u32(offset)?,
+            ));
+        }
+
+        rooms.push(RoomSpoiler { name, warps, items });
+    }
+
+    let read_shop = |slots: &mut dyn Iterator<Item = u32>| -> Result<Vec<ItemSpoiler>, RandomizerError> {
+        slots
+            .map(|slot| Ok(ItemSpoiler {
+                // the price comes after the item id
+                original_price: Some(original.read_u32(slot + 4)?),
+                price: Some(patched.read_u32(slot + 4)?),
+                ..ItemSpoiler::new(slot, original.read_u32(slot)?, patched.read_u32(slot)?)
+            }))
+            .collect()
+    };
+
+    Ok(Spoiler {
+        seed: settings.seed,
+        hash,
+        permalink: permalink::encode(settings),
+        settings: settings.clone(),
+        rooms,
+        rowf: read_shop(&mut get_rowf_iter())?,
+        merlow: read_shop(&mut get_merlow_iter())?,
+    })
+}
+
+impl fmt::Display for ItemSpoiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}: {} ({:#06x})", self.ptr, self.original, self.original_id)?;
+        if let Some(price) = self.original_price {
+            write!(f, " for {}", price)?;
+        }
+        write!(f, " -> {} ({:#06x})", self.name, self.id)?;
+        if let Some(price) = self.price {
+            write!(f, " for {}", price)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Spoiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed: {}", self.seed)?;
+        writeln!(f, "hash: {}", self.hash)?;
+        writeln!(f, "permalink: {}", self.permalink)?;
+        writeln!(f, "settings: {}", serde_json::to_string(&self.settings).map_err(|_| fmt::Error)?)?;
+
+        for room in self.rooms.iter() {
+            writeln!(f)?;
+            writeln!(f, "{}", room.name)?;
+            for warp in room.warps.iter() {
+                writeln!(
+                    f,
+                    "    warp {:#010x}: {} entrance {} -> {} entrance {}",
+                    warp.warp_ptr,
+                    warp.original_room,
+                    warp.original_entrance,
+                    warp.room,
+                    warp.entrance
+                )?;
+            }
+            for item in room.items.iter() {
+                writeln!(f, "    item {}", item)?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Rowf's shop")?;
+        for item in self.rowf.iter() {
+            writeln!(f, "    {}", item)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Merlow's shop")?;
+        for item in self.merlow.iter() {
+            writeln!(f, "    {}", item)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+// Checks that the input is a rom we know the layout of, since all of our offsets are hard-coded.
+
+use crate::checksum::crc32;
+use crate::RandomizerError;
+
+pub struct KnownRom {
+    pub name: &'static str,
+    pub sha1: [u8; 20],
+    pub crc32: u32,
+}
+
+/// Clean, big-endian dumps that the hard-coded offsets were found in. Source: No-Intro.
+pub const KNOWN_ROMS: [KnownRom; 1] = [
+    KnownRom {
+        name: "Paper Mario (USA)",
+        sha1: [
+            0x38, 0x37, 0xf4, 0x4c, 0xda, 0x78, 0x4b, 0x46, 0x6c, 0x9a,
+            0x2d, 0x99, 0xdf, 0x70, 0xd7, 0x7c, 0x32, 0x2b, 0x97, 0xa0,
+        ],
+        crc32: 0xA7F5_CD7E,
+    },
+];
+
+/// Places we patch with these exact words. A vanilla rom has something else there.
+const PATCH_MARKERS: [(usize, u32); 2] = [
+    // start with Goombario out
+    (0x808A8, 0xA082_0012),
+    // enable menus
+    (0x168074, 0x2406_FF81),
+];
+
+/// Check that `rom`, which should already be big-endian, is one of the `KNOWN_ROMS`.
+pub fn check(rom: &[u8]) -> Result<&'static KnownRom, RandomizerError> {
+    let sha1 = sha1(rom);
+    if let Some(known) = KNOWN_ROMS.iter().find(|known| known.sha1 == sha1) {
+        return Ok(known);
+    }
+
+    let looks_patched = PATCH_MARKERS.iter().all(|&(offset, word)| {
+        rom.get(offset..offset + 4) == Some(&word.to_be_bytes()[..])
+    });
+
+    let problem = if looks_patched {
+        "This looks like an already-randomized ROM"
+    } else {
+        "This is not a clean USA ROM"
+    };
+
+    Err(RandomizerError::WrongRom(format!(
+        "{} (sha1 {}, crc32 {:08x}). Expected {} (sha1 {}, crc32 {:08x}).",
+        problem,
+        hex(&sha1),
+        crc32(rom),
+        KNOWN_ROMS[0].name,
+        hex(&KNOWN_ROMS[0].sha1),
+        KNOWN_ROMS[0].crc32,
+    )))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A plain SHA-1 as described in FIPS 180-4.
+pub fn sha1(bytes: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+
+    let bit_len = (bytes.len() as u64).wrapping_mul(8);
+    let mut tail = Vec::with_capacity(128);
+    let full_blocks_len = bytes.len() - bytes.len() % 64;
+    tail.extend_from_slice(&bytes[full_blocks_len..]);
+    tail.push(0x80);
+    while tail.len() % 64 != 56 {
+        tail.push(0);
+    }
+    tail.extend_from_slice(&bit_len.to_be_bytes());
+
+    for block in bytes[..full_blocks_len].chunks_exact(64).chain(tail.chunks_exact(64)) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &w_i) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w_i);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h_i, x) in h.iter_mut().zip([a, b, c, d, e].iter()) {
+            *h_i = h_i.wrapping_add(*x);
+        }
+    }
+
+    let mut output = [0u8; 20];
+    for (chunk, h_i) in output.chunks_exact_mut(4).zip(h.iter()) {
+        chunk.copy_from_slice(&h_i.to_be_bytes());
+    }
+    output
+}
+
+#[test]
+fn sha1_test_vectors() {
+    assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
@@ -0,0 +1,118 @@
+// The rooms and the warps between them, in a form that can be saved and drawn, so a seed's map
+// can be looked at without playing it.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::spoiler::{RoomSpoiler, Spoiler};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WarpGraph {
+    /// Every room, in room table order.
+    pub rooms: Vec<String>,
+    pub warps: Vec<WarpEdge>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WarpEdge {
+    pub from: String,
+    pub warp_ptr: u32,
+    pub to: String,
+    pub entrance: u32,
+}
+
+impl WarpGraph {
+    /// Rooms which appear in the room table more than once are only included the first time.
+    fn from_rooms(rooms: &[RoomSpoiler], randomized: bool) -> Self {
+        let mut seen = HashSet::with_capacity(rooms.len());
+        let mut graph = WarpGraph { rooms: Vec::with_capacity(rooms.len()), warps: Vec::new() };
+        for room in rooms.iter() {
+            if !seen.insert(room.name.as_str()) {
+                continue;
+            }
+            graph.rooms.push(room.name.clone());
+            for warp in room.warps.iter() {
+                let (to, entrance) = if randomized {
+                    (&warp.room, warp.entrance)
+                } else {
+                    (&warp.original_room, warp.original_entrance)
+                };
+                graph.warps.push(WarpEdge {
+                    from: room.name.clone(),
+                    warp_ptr: warp.warp_ptr,
+                    to: to.clone(),
+                    entrance,
+                });
+            }
+        }
+        graph
+    }
+
+    /// The graph in Graphviz's DOT language, with each warp labelled with its pointer and the
+    /// entrance it leads to.
+    pub fn to_dot(&self) -> String {
+        // Room names are letters, digits and underscores, but quoting them costs nothing.
+        let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph rooms {\n");
+        for room in self.rooms.iter() {
+            let _ = writeln!(dot, "    {};", quote(room));
+        }
+        for warp in self.warps.iter() {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label=\"{:#010x}: entrance {}\"];",
+                quote(&warp.from),
+                quote(&warp.to),
+                warp.warp_ptr,
+                warp.entrance
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Spoiler {
+    /// The warps as they are in the unmodified game.
+    pub fn vanilla_graph(&self) -> WarpGraph {
+        WarpGraph::from_rooms(&self.rooms, false)
+    }
+
+    /// The warps as they are in the randomized rom.
+    pub fn randomized_graph(&self) -> WarpGraph {
+        WarpGraph::from_rooms(&self.rooms, true)
+    }
+}
+
+#[test]
+fn graphs_hold_each_room_once_and_every_warp() {
+    use crate::spoiler::WarpSpoiler;
+
+    let room = |name: &str, warps: Vec<WarpSpoiler>| RoomSpoiler { name: name.to_owned(), warps, items: Vec::new() };
+    let warp = |warp_ptr, original_room: &str, room: &str| WarpSpoiler {
+        warp_ptr,
+        original_room: original_room.to_owned(),
+        original_entrance: 0,
+        room: room.to_owned(),
+        entrance: 1,
+    };
+    let rooms = [
+        room("kmr_00", vec![warp(0x8024_0010, "kmr_01", "kmr_04")]),
+        room("kmr_01", vec![warp(0x8024_0020, "kmr_00", "kmr_00")]),
+        room("kmr_00", vec![warp(0x8024_0010, "kmr_01", "kmr_04")]),
+        room("kmr_04", Vec::new()),
+    ];
+
+    let vanilla = WarpGraph::from_rooms(&rooms, false);
+    assert_eq!(vanilla.rooms, vec!["kmr_00", "kmr_01", "kmr_04"]);
+    assert_eq!(vanilla.warps.len(), 2);
+    assert_eq!(vanilla.warps[0].to, "kmr_01");
+
+    let randomized = WarpGraph::from_rooms(&rooms, true);
+    assert_eq!(randomized.warps[0].to, "kmr_04");
+    assert_eq!(randomized.warps[0].entrance, 1);
+    assert!(randomized.to_dot().contains("\"kmr_00\" -> \"kmr_04\" [label=\"0x80240010: entrance 1\"];"));
+}
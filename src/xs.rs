@@ -0,0 +1,237 @@
+use std::num::Wrapping;
+
+use crate::RngVersion;
+
+pub struct Xs {
+    state: [Wrapping<u32>; 4],
+    version: RngVersion,
+}
+
+/// Split the seed into the four words of state. xorshift never leaves the all-zero state, so
+/// that is replaced with something else.
+pub fn xs_from_seed(seed: u128, version: RngVersion) -> Xs {
+    let seed = if seed == 0 { 0x9E37_79B9_7F4A_7C15_F39C_C060_5CED_C834 } else { seed };
+    Xs {
+        state: [
+            Wrapping((seed & 0xFFFF_FFFF) as u32),
+            Wrapping(((seed >> 32) & 0xFFFF_FFFF) as u32),
+            Wrapping(((seed >> 64) & 0xFFFF_FFFF) as u32),
+            Wrapping(((seed >> 96) & 0xFFFF_FFFF) as u32),
+        ],
+        version,
+    }
+}
+
+/// The parts of the randomizer that each get their own stream of random numbers, so that
+/// changing the settings for one part does not change what the others do with the same seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Rooms,
+    MapItems,
+    Rowf,
+    Merlow,
+    Start,
+    Prices,
+}
+
+impl Stream {
+    /// What the stream's seed is made from, along with the master seed. These must never change,
+    /// since changing one changes every rom made with that stream.
+    fn label(self) -> &'static [u8] {
+        match self {
+            Stream::Rooms => b"rooms",
+            Stream::MapItems => b"map items",
+            Stream::Rowf => b"rowf",
+            Stream::Merlow => b"merlow",
+            Stream::Start => b"start",
+            Stream::Prices => b"prices",
+        }
+    }
+}
+
+pub struct Streams {
+    /// With `RngVersion::Legacy` there is only one stream, shared by everything, as there was
+    /// before streams were split up.
+    streams: Vec<Xs>,
+}
+
+pub fn xs_streams(seed: u128, version: RngVersion) -> Streams {
+    let streams = match version {
+        RngVersion::Legacy => vec![xs_from_seed(seed, version)],
+        RngVersion::Uniform => [Stream::Rooms, Stream::MapItems, Stream::Rowf, Stream::Merlow, Stream::Start, Stream::Prices]
+            .iter()
+            .map(|stream| {
+                let mut bytes = seed.to_le_bytes().to_vec();
+                bytes.extend_from_slice(stream.label());
+                let digest = crate::verify::sha1(&bytes);
+                let mut stream_seed = [0; 16];
+                stream_seed.copy_from_slice(&digest[..16]);
+                xs_from_seed(u128::from_le_bytes(stream_seed), version)
+            })
+            .collect(),
+    };
+    Streams { streams }
+}
+
+impl Streams {
+    pub fn get(&mut self, stream: Stream) -> &mut Xs {
+        let index = if self.streams.len() == 1 { 0 } else { stream as usize };
+        &mut self.streams[index]
+    }
+}
+
+pub fn xorshift(xs: &mut Xs) -> u32 {
+    let xs = &mut xs.state;
+    let mut t = xs[3];
+
+    xs[3] = xs[2];
+    xs[2] = xs[1];
+    xs[1] = xs[0];
+
+    t ^= t << 11;
+    t ^= t >> 8;
+    xs[0] = t ^ xs[0] ^ (xs[0] >> 19);
+
+    xs[0].0
+}
+
+pub fn xs_u32(xs: &mut Xs, min: u32, one_past_max: u32) -> u32 {
+    let range = one_past_max - min;
+    match xs.version {
+        RngVersion::Legacy => (xorshift(xs) % range) + min,
+        RngVersion::Uniform => {
+            // Values at or above the largest multiple of `range` would make the smaller
+            // results more likely, so we draw again if we get one of those.
+            let range = u64::from(range);
+            let limit = (1 << 32) / range * range;
+            loop {
+                let x = u64::from(xorshift(xs));
+                if x < limit {
+                    return (x % range) as u32 + min;
+                }
+            }
+        }
+    }
+}
+
+pub fn xs_choice_str<'a>(xs: &mut Xs, slice: &[&'a str]) -> &'a str {
+    let len = slice.len();
+    assert!(len < u32::MAX as _);
+    slice[xs_u32(xs,0, len as u32) as usize]
+}
+
+pub fn xs_choice(xs: &mut Xs, slice: &[u32]) -> u32 {
+    let len = slice.len();
+    assert!(len < u32::MAX as _);
+    slice[xs_u32(xs, 0, len as u32) as usize]
+}
+
+pub fn xs_shuffle(xs: &mut Xs, v: &mut [u32]) {
+    let len = v.len();
+    if len < 2 {
+        return;
+    }
+    let last = match xs.version {
+        // This stops a step early, so it is not uniform, (and never changes a pair,) but old
+        // seeds depend on it.
+        RngVersion::Legacy => len - 2,
+        RngVersion::Uniform => len - 1,
+    };
+    for i in 0..last {
+        // Oh gee this won't shuffle the whole vec if there are ever 2^32 elements. Meh.
+        let j = xs_u32(xs,i as u32, len as u32) as usize;
+        v.swap(i, j);
+    }
+}
+
+#[test]
+fn xs_u32_is_uniform() {
+    let xs = &mut xs_from_seed(42, RngVersion::Uniform);
+
+    // 5 does not divide 2^32, so the legacy version would be biased here, though by too
+    // little to see in a test of this size.
+    const SAMPLES: usize = 100_000;
+    let mut counts = [0; 5];
+    for _ in 0..SAMPLES {
+        counts[xs_u32(xs, 10, 15) as usize - 10] += 1;
+    }
+
+    // 5% of the expected count is around eight standard deviations here.
+    let expected = SAMPLES / counts.len();
+    for &count in counts.iter() {
+        assert!((count as isize - expected as isize).abs() < expected as isize / 20, "{:?}", counts);
+    }
+}
+
+#[test]
+fn xs_shuffle_gives_every_permutation_equally() {
+    let xs = &mut xs_from_seed(42, RngVersion::Uniform);
+
+    const SAMPLES: usize = 60_000;
+    let mut counts = std::collections::HashMap::new();
+    for _ in 0..SAMPLES {
+        let mut v = [0, 1, 2];
+        xs_shuffle(xs, &mut v);
+        *counts.entry(v).or_insert(0) += 1;
+    }
+
+    assert_eq!(counts.len(), 6, "{:?}", counts);
+    // 5% of the expected count is over five standard deviations here.
+    let expected = SAMPLES / 6;
+    for &count in counts.values() {
+        assert!((count as isize - expected as isize).abs() < expected as isize / 20, "{:?}", counts);
+    }
+}
+
+#[test]
+fn legacy_rng_is_unchanged() {
+    // The first values from the unfixed helpers, which --legacy-rng keeps.
+    let xs = &mut xs_from_seed(42, RngVersion::Legacy);
+    let values: Vec<u32> = (0..4).map(|_| xs_u32(xs, 1, 0x16C)).collect();
+    let mut state = [Wrapping(42u32), Wrapping(0), Wrapping(0), Wrapping(0)];
+    let expected: Vec<u32> = (0..4).map(|_| {
+        let mut t = state[3];
+        state[3] = state[2];
+        state[2] = state[1];
+        state[1] = state[0];
+        t ^= t << 11;
+        t ^= t >> 8;
+        state[0] = t ^ state[0] ^ (state[0] >> 19);
+        state[0].0 % (0x16C - 1) + 1
+    }).collect();
+    assert_eq!(values, expected);
+
+    let mut v = [0, 1];
+    for _ in 0..100 {
+        xs_shuffle(xs, &mut v);
+        assert_eq!(v, [0, 1]);
+    }
+}
+
+#[test]
+fn streams_are_independent() {
+    let mut streams = xs_streams(42, RngVersion::Uniform);
+    let rowf_first = xorshift(streams.get(Stream::Rowf));
+    // the labels must not change, or every seed would
+    assert_eq!(rowf_first, 3_690_292_652);
+
+    let mut streams = xs_streams(42, RngVersion::Uniform);
+    for _ in 0..100 {
+        xorshift(streams.get(Stream::Rooms));
+        xorshift(streams.get(Stream::MapItems));
+    }
+    assert_eq!(xorshift(streams.get(Stream::Rowf)), rowf_first);
+    assert_ne!(xorshift(streams.get(Stream::Merlow)), rowf_first);
+
+    let mut legacy = xs_streams(42, RngVersion::Legacy);
+    let xs = &mut xs_from_seed(42, RngVersion::Legacy);
+    xorshift(legacy.get(Stream::Rooms));
+    xorshift(xs);
+    assert_eq!(xorshift(legacy.get(Stream::Rowf)), xorshift(xs));
+}
+
+#[test]
+fn the_all_zero_state_is_avoided() {
+    let xs = &mut xs_from_seed(0, RngVersion::Uniform);
+    assert!((0..4).any(|_| xorshift(xs) != 0));
+}